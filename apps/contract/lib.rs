@@ -6,10 +6,52 @@ mod eduverse {
     use ink::prelude::string::String;
     use ink::prelude::string::ToString;
     use ink::prelude::vec::Vec;
-    use ink::storage::Mapping;
+    use ink::storage::traits::StorageKey;
+    use ink::storage::{Lazy, Mapping};
     use psp34::PSP34Error;
     use psp34::{Id, PSP34Data};
 
+    /// Version of the storage layout this code expects, bumped on every layout change
+    pub const STORAGE_VERSION: u16 = 6;
+    /// Upper bound for the platform fee, in basis points (10%)
+    pub const MAX_PLATFORM_FEE_BPS: u16 = 1_000;
+    /// Basis point denominator used for fee calculations
+    const BPS_DENOMINATOR: Balance = 10_000;
+    /// Maximum number of students completed in one `complete_course_batch` call
+    pub const MAX_COMPLETION_BATCH: usize = 50;
+    /// Maximum number of payees sharing a course's revenue
+    pub const MAX_PAYEES: usize = 10;
+    /// Maximum number of prerequisites of a single course
+    pub const MAX_PREREQUISITES: usize = 10;
+    /// Maximum number of lessons in a single course
+    pub const MAX_LESSONS: u32 = 100;
+    /// Maximum number of assignments in a single course
+    pub const MAX_ASSIGNMENTS: u32 = 50;
+    /// Maximum number of quizzes in a single course
+    pub const MAX_QUIZZES: u32 = 50;
+    /// Maximum number of questions in a single quiz
+    pub const MAX_QUIZ_QUESTIONS: u32 = 100;
+    /// Time students have to reveal quiz answers once the answer key is revealed
+    pub const QUIZ_REVEAL_WINDOW: Timestamp = 86_400_000;
+    /// Maximum number of live sessions in a single course
+    pub const MAX_SESSIONS: u32 = 100;
+    /// Maximum number of students recorded in one `record_attendance` call
+    pub const MAX_ATTENDANCE_BATCH: usize = 100;
+    /// Maximum number of courses returned by one `get_courses_paged` call
+    pub const MAX_PAGE_SIZE: u32 = 20;
+    /// Maximum number of course ids inspected by one `get_courses_paged` call
+    pub const MAX_PAGE_SCAN: u32 = 200;
+    /// Maximum number of waitlist positions visited by one promotion
+    pub const MAX_WAITLIST_STEPS: u32 = 50;
+    /// Time a waitlisted student has to enroll into a seat offered to them
+    pub const SEAT_OFFER_WINDOW: Timestamp = 86_400_000;
+    /// Number of courses rewritten by one `migrate` call
+    pub const MIGRATION_BATCH: u32 = 20;
+    /// Name of the certificate NFT collection
+    const COLLECTION_NAME: &str = "Eduverse Certificates";
+    /// Symbol of the certificate NFT collection
+    const COLLECTION_SYMBOL: &str = "EDUC";
+
     ////////////////////////////////////
     ////////// MODELS /////////////////
     //////////////////////////////////
@@ -32,6 +74,126 @@ mod eduverse {
         pub active: bool,
         pub metadata_hash: String,
         pub created_at: Timestamp,
+        /// Period after `start_time` during which enrolled students may still request a refund
+        pub refund_window: Timestamp,
+        /// Platform fee in basis points, fixed when the course is created
+        pub platform_fee_bps: u16,
+        /// Set by `cancel_course`, a cancelled course stays inactive and cannot be reopened,
+        /// its students take their payment back with `request_refund`
+        pub cancelled: bool,
+        /// PSP22 token the price is denominated in, `None` for the native currency
+        pub payment_token: Option<AccountId>,
+        /// Revenue split of released payments, empty when the teacher receives everything
+        pub payees: Vec<Payee>,
+        /// Earlier courses a student must have completed to enroll
+        pub prerequisites: Vec<u32>,
+        /// Criteria for students to claim their own certificate, `None` leaves
+        /// completion to the teacher
+        pub completion_rule: Option<CompletionRule>,
+    }
+
+    #[ink::scale_derive(Encode, Decode, TypeInfo)]
+    #[cfg_attr(
+        feature = "std",
+        derive(ink::storage::traits::StorageLayout, Debug, PartialEq, Eq)
+    )]
+    #[derive(Clone)]
+    pub struct CompletionRule {
+        /// Minimum aggregate score over assignments and revealed quizzes, in basis points
+        pub min_score: Option<u32>,
+        /// Whether every lesson must be marked complete
+        pub all_lessons: bool,
+        /// Minimum number of live sessions attended
+        pub min_attendance: Option<u32>,
+    }
+
+    #[ink::scale_derive(Encode, Decode, TypeInfo)]
+    #[cfg_attr(
+        feature = "std",
+        derive(ink::storage::traits::StorageLayout, Debug, PartialEq, Eq)
+    )]
+    #[derive(Clone)]
+    pub struct Session {
+        pub title: String,
+        /// Attendance can be recorded from this time on
+        pub starts_at: Timestamp,
+    }
+
+    #[ink::scale_derive(Encode, Decode, TypeInfo)]
+    #[cfg_attr(
+        feature = "std",
+        derive(ink::storage::traits::StorageLayout, Debug, PartialEq, Eq)
+    )]
+    #[derive(Clone)]
+    pub struct Payee {
+        pub account: AccountId,
+        pub share_bps: u16,
+    }
+
+    #[ink::scale_derive(Encode, Decode, TypeInfo)]
+    #[cfg_attr(
+        feature = "std",
+        derive(ink::storage::traits::StorageLayout, Debug, PartialEq, Eq)
+    )]
+    #[derive(Clone)]
+    pub struct Lesson {
+        /// Module the lesson belongs to, lessons are ordered by their ID
+        pub module: u32,
+        pub title: String,
+        pub content_hash: String,
+        /// Earliest time students can complete the lesson
+        pub unlock_time: Option<Timestamp>,
+    }
+
+    #[ink::scale_derive(Encode, Decode, TypeInfo)]
+    #[cfg_attr(
+        feature = "std",
+        derive(ink::storage::traits::StorageLayout, Debug, PartialEq, Eq)
+    )]
+    #[derive(Clone)]
+    pub struct Assignment {
+        pub title: String,
+        /// Submissions after this time are accepted but flagged as late
+        pub deadline: Timestamp,
+        pub max_score: u32,
+        /// Relative weight in the aggregate score
+        pub weight: u32,
+    }
+
+    #[ink::scale_derive(Encode, Decode, TypeInfo)]
+    #[cfg_attr(
+        feature = "std",
+        derive(ink::storage::traits::StorageLayout, Debug, PartialEq, Eq)
+    )]
+    #[derive(Clone)]
+    pub struct Submission {
+        pub content_hash: String,
+        pub submitted_at: Timestamp,
+        pub late: bool,
+        /// Score out of the assignment's `max_score`, `None` until graded
+        pub score: Option<u32>,
+        pub graded_by: Option<AccountId>,
+    }
+
+    #[ink::scale_derive(Encode, Decode, TypeInfo)]
+    #[cfg_attr(
+        feature = "std",
+        derive(ink::storage::traits::StorageLayout, Debug, PartialEq, Eq)
+    )]
+    #[derive(Clone)]
+    pub struct Quiz {
+        pub question_count: u32,
+        /// Relative weight in the aggregate score, alongside assignment weights
+        pub weight: u32,
+        /// Commitment to the answer key, see `quiz_commitment`
+        pub key_commitment: Hash,
+        /// Students commit answers until this time, the key is revealed after it
+        pub commit_deadline: Timestamp,
+        /// The teacher reveals the answer key until this time, students then get
+        /// `QUIZ_REVEAL_WINDOW` to reveal their answers
+        pub reveal_deadline: Timestamp,
+        /// One answer byte per question, set once the teacher revealed it
+        pub answer_key: Option<Vec<u8>>,
     }
 
     #[ink::scale_derive(Encode, Decode, TypeInfo)]
@@ -47,6 +209,178 @@ mod eduverse {
         pub completion_date: Timestamp,
     }
 
+    #[ink::scale_derive(Encode, Decode, TypeInfo)]
+    #[cfg_attr(
+        feature = "std",
+        derive(ink::storage::traits::StorageLayout, Debug, PartialEq, Eq)
+    )]
+    #[derive(Clone)]
+    pub struct RevocationRecord {
+        pub revoked_by: AccountId,
+        pub reason_hash: String,
+        pub revoked_at: Timestamp,
+    }
+
+    #[ink::scale_derive(Encode, Decode, TypeInfo)]
+    #[cfg_attr(
+        feature = "std",
+        derive(ink::storage::traits::StorageLayout, Debug, PartialEq, Eq)
+    )]
+    #[derive(Clone, Copy)]
+    pub enum EnrollmentStatus {
+        Active,
+        /// Left the course before it started
+        Dropped,
+        /// Paid back through a refund or a cancelled course
+        Refunded,
+        Completed,
+        /// Marked as not passing by the teacher or a grader
+        Failed,
+    }
+
+    #[ink::scale_derive(Encode, Decode, TypeInfo)]
+    #[cfg_attr(
+        feature = "std",
+        derive(ink::storage::traits::StorageLayout, Debug, PartialEq, Eq)
+    )]
+    #[derive(Clone)]
+    pub struct EnrollmentRecord {
+        pub enrolled_at: Timestamp,
+        pub amount_paid: Balance,
+        /// PSP22 token the price was paid in, `None` for the native currency
+        pub payment_asset: Option<AccountId>,
+        pub status: EnrollmentStatus,
+    }
+
+    #[ink::scale_derive(Encode, Decode, TypeInfo)]
+    #[cfg_attr(
+        feature = "std",
+        derive(ink::storage::traits::StorageLayout, Debug, PartialEq, Eq)
+    )]
+    #[derive(Clone)]
+    pub struct WaitlistEntry {
+        /// Place in the course's waitlist, lower positions are promoted first
+        pub position: u32,
+        /// Price held in escrow, in the course's payment asset
+        pub escrowed: Balance,
+        pub joined_at: Timestamp,
+    }
+
+    #[ink::scale_derive(Encode, Decode, TypeInfo)]
+    #[cfg_attr(
+        feature = "std",
+        derive(ink::storage::traits::StorageLayout, Debug, PartialEq, Eq)
+    )]
+    #[derive(Clone)]
+    struct EnrollmentIndex {
+        /// Position of the student in the course's student index
+        course_index: u32,
+        /// Position of the course in the student's course index
+        student_index: u32,
+    }
+
+    #[ink::scale_derive(Encode, Decode, TypeInfo)]
+    #[cfg_attr(
+        feature = "std",
+        derive(ink::storage::traits::StorageLayout, Debug, PartialEq, Eq)
+    )]
+    #[derive(Clone, Copy)]
+    pub enum CourseRole {
+        /// May update the course alongside the teacher
+        CoTeacher,
+        /// May grade submissions and record session attendance alongside the teacher
+        TeachingAssistant,
+        /// May mark students as having completed the course
+        Grader,
+    }
+
+    /// Criteria for `get_courses_paged`, unset fields match every course
+    #[ink::scale_derive(Encode, Decode, TypeInfo)]
+    #[cfg_attr(feature = "std", derive(Debug, PartialEq, Eq))]
+    #[derive(Clone, Default)]
+    pub struct CourseFilter {
+        pub active_only: bool,
+        pub upcoming_only: bool,
+        pub teacher: Option<AccountId>,
+        pub min_price: Option<Balance>,
+        pub max_price: Option<Balance>,
+        pub free_only: bool,
+    }
+
+    impl CourseFilter {
+        fn matches(&self, course: &Course, current_time: Timestamp) -> bool {
+            if self.active_only && (!course.active || course.cancelled) {
+                return false;
+            }
+            if self.upcoming_only && course.start_time <= current_time {
+                return false;
+            }
+            if self
+                .teacher
+                .is_some_and(|teacher| teacher != course.teacher)
+            {
+                return false;
+            }
+            if self.min_price.is_some_and(|min| course.price < min) {
+                return false;
+            }
+            if self.max_price.is_some_and(|max| course.price > max) {
+                return false;
+            }
+            !(self.free_only && course.price > 0)
+        }
+    }
+
+    #[ink::scale_derive(Encode, Decode, TypeInfo)]
+    #[cfg_attr(feature = "std", derive(Debug, PartialEq, Eq))]
+    #[derive(Clone)]
+    pub struct CoursePage {
+        pub courses: Vec<Course>,
+        /// Offset to pass for the next page, `None` once every course was scanned
+        pub next_cursor: Option<u32>,
+    }
+
+    /// Outcome for a single student of `complete_course_batch`
+    #[ink::scale_derive(Encode, Decode, TypeInfo)]
+    #[cfg_attr(feature = "std", derive(Debug, PartialEq, Eq))]
+    #[derive(Clone)]
+    pub enum CompletionResult {
+        Completed(psp34::Id),
+        NotEnrolled,
+        AlreadyCompleted,
+        /// The enrollment was marked as failed
+        NotActive,
+    }
+
+    /// Error type of the PSP22 standard, used to decode token contract replies
+    #[derive(Debug, PartialEq, Eq)]
+    #[ink::scale_derive(Encode, Decode, TypeInfo)]
+    pub enum PSP22Error {
+        Custom(String),
+        InsufficientBalance,
+        InsufficientAllowance,
+        ZeroRecipientAddress,
+        ZeroSenderAddress,
+        SafeTransferCheckFailed(String),
+    }
+
+    /// Undecoded bytes of a storage entry, read when rewriting an older layout
+    struct RawEntry(Vec<u8>);
+
+    impl scale::Decode for RawEntry {
+        fn decode<I: scale::Input>(input: &mut I) -> Result<Self, scale::Error> {
+            let mut bytes = ink::prelude::vec![0; input.remaining_len()?.unwrap_or(0)];
+            input.read(&mut bytes)?;
+            Ok(Self(bytes))
+        }
+    }
+
+    impl scale::Encode for RawEntry {
+        fn encode_to<T: scale::Output + ?Sized>(&self, dest: &mut T) {
+            dest.write(&self.0);
+        }
+    }
+
     ////////////////////////////////////
     ////////// EVENTS /////////////////
     //////////////////////////////////
@@ -67,6 +401,8 @@ mod eduverse {
         #[ink(topic)]
         pub student: AccountId,
         pub payment: Balance,
+        /// Amount sent above `payment`, credited back to the student's withdrawable balance
+        pub excess: Balance,
     }
 
     #[ink(event)]
@@ -78,6 +414,27 @@ mod eduverse {
         pub certificate_id: psp34::Id,
     }
 
+    #[ink(event)]
+    pub struct StudentFailed {
+        #[ink(topic)]
+        pub course_id: u32,
+        #[ink(topic)]
+        pub student: AccountId,
+        pub failed_by: AccountId,
+    }
+
+    #[ink(event)]
+    pub struct CertificateRevoked {
+        #[ink(topic)]
+        pub certificate_id: psp34::Id,
+        #[ink(topic)]
+        pub course_id: u32,
+        #[ink(topic)]
+        pub student: AccountId,
+        pub revoked_by: AccountId,
+        pub reason_hash: String,
+    }
+
     #[ink(event)]
     pub struct CourseUpdated {
         #[ink(topic)]
@@ -87,98 +444,504 @@ mod eduverse {
     }
 
     #[ink(event)]
-    pub struct PSP34Transfer {
-        #[ink(topic)]
-        pub from: Option<AccountId>,
+    pub struct LessonAdded {
         #[ink(topic)]
-        pub to: Option<AccountId>,
+        pub course_id: u32,
+        pub lesson_id: u32,
+        pub module: u32,
+    }
+
+    #[ink(event)]
+    pub struct LessonUpdated {
         #[ink(topic)]
-        pub id: Id,
+        pub course_id: u32,
+        pub lesson_id: u32,
     }
 
     #[ink(event)]
-    pub struct PSP34Approval {
+    pub struct LessonCompleted {
         #[ink(topic)]
-        pub owner: AccountId,
+        pub course_id: u32,
         #[ink(topic)]
-        pub operator: AccountId,
+        pub student: AccountId,
+        pub lesson_id: u32,
+    }
+
+    #[ink(event)]
+    pub struct AssignmentAdded {
         #[ink(topic)]
-        pub id: Option<Id>,
-        pub approved: bool,
+        pub course_id: u32,
+        pub assignment_id: u32,
+        pub deadline: Timestamp,
     }
 
     #[ink(event)]
-    pub struct PSP34AttributeSet {
+    pub struct AssignmentSubmitted {
         #[ink(topic)]
-        pub id: Id,
-        pub key: Vec<u8>,
-        pub data: Vec<u8>,
+        pub course_id: u32,
+        #[ink(topic)]
+        pub student: AccountId,
+        pub assignment_id: u32,
+        pub content_hash: String,
+        pub late: bool,
     }
 
-    ////////////////////////////////////
-    ////////// ERRORS /////////////////
-    //////////////////////////////////
-    #[derive(Debug, PartialEq, Eq)]
-    #[ink::scale_derive(Encode, Decode, TypeInfo)]
-    pub enum Error {
-        CourseNotActive,
-        NotOwner,
-        NotTeacher,
-        NotStudent,
-        CourseNotFound,
-        CourseInactive,
-        CourseFull,
-        CourseNotStarted,
-        CourseEnded,
-        AlreadyEnrolled,
-        NotEnrolled,
-        InsufficientPayment,
-        InvalidInput,
-        InvalidTime,
-        AlreadyCompleted,
-        CertificateNotFound,
-        TransferError,
-        PSP34Error(PSP34Error),
-        CourseIsFull,
-        CourseInProgress,
-        Unauthorized,
-        NFTMintingFailed,
-        PaymentFailed,
-        RefundPeriodEnded,
-        InvalidFeePercentage,
-        NoRefundAvailable,
+    #[ink(event)]
+    pub struct SubmissionGraded {
+        #[ink(topic)]
+        pub course_id: u32,
+        #[ink(topic)]
+        pub student: AccountId,
+        pub assignment_id: u32,
+        pub score: u32,
+        pub graded_by: AccountId,
     }
 
-    #[ink(storage)]
-    pub struct Eduverse {
-        /// Course counter for generating course IDs
-        course_counter: u32,
-        /// Mapping of course ID to course details
-        courses: Mapping<u32, Course>,
-        /// Mapping of student address to their enrollments
-        student_enrollments: Mapping<AccountId, Vec<u32>>,
-        /// Mapping of teacher address to their courses
-        teacher_courses: Mapping<AccountId, Vec<u32>>,
-        /// Mapping of course ID to enrolled students
-        course_students: Mapping<u32, Vec<AccountId>>,
-        /// Mapping of course ID and student to completion status
-        course_completions: Mapping<(u32, AccountId), bool>,
-        /// Contract owner
-        owner: AccountId,
+    #[ink(event)]
+    pub struct QuizCreated {
+        #[ink(topic)]
+        pub course_id: u32,
+        pub quiz_id: u32,
+        pub commit_deadline: Timestamp,
+        pub reveal_deadline: Timestamp,
+    }
 
-        // NFT functionality
-        data: PSP34Data,
-        certificate_info: Mapping<Id, CertificateInfo>,
-        // Custom metadata storage
-        attributes: Mapping<(psp34::Id, Vec<u8>), Vec<u8>>,
-        // Track certificates by student
-        student_certificates: Mapping<AccountId, Vec<psp34::Id>>,
+    #[ink(event)]
+    pub struct QuizAnswersCommitted {
+        #[ink(topic)]
+        pub course_id: u32,
+        #[ink(topic)]
+        pub student: AccountId,
+        pub quiz_id: u32,
     }
 
-    impl Default for Eduverse {
-        fn default() -> Self {
-            Self::new()
-        }
+    #[ink(event)]
+    pub struct QuizKeyRevealed {
+        #[ink(topic)]
+        pub course_id: u32,
+        pub quiz_id: u32,
+    }
+
+    #[ink(event)]
+    pub struct QuizScored {
+        #[ink(topic)]
+        pub course_id: u32,
+        #[ink(topic)]
+        pub student: AccountId,
+        pub quiz_id: u32,
+        pub score: u32,
+    }
+
+    #[ink(event)]
+    pub struct SessionAdded {
+        #[ink(topic)]
+        pub course_id: u32,
+        pub session_id: u32,
+        pub starts_at: Timestamp,
+    }
+
+    #[ink(event)]
+    pub struct AttendanceRecorded {
+        #[ink(topic)]
+        pub course_id: u32,
+        pub session_id: u32,
+        #[ink(topic)]
+        pub recorded_by: AccountId,
+        pub students: Vec<AccountId>,
+    }
+
+    #[ink(event)]
+    pub struct AttendanceOracleUpdated {
+        #[ink(topic)]
+        pub oracle: AccountId,
+        pub enabled: bool,
+    }
+
+    #[ink(event)]
+    pub struct RoleGranted {
+        #[ink(topic)]
+        pub course_id: u32,
+        #[ink(topic)]
+        pub account: AccountId,
+        pub role: CourseRole,
+    }
+
+    #[ink(event)]
+    pub struct RoleRevoked {
+        #[ink(topic)]
+        pub course_id: u32,
+        #[ink(topic)]
+        pub account: AccountId,
+        pub role: CourseRole,
+    }
+
+    #[ink(event)]
+    pub struct CourseCancelled {
+        #[ink(topic)]
+        pub course_id: u32,
+        #[ink(topic)]
+        pub cancelled_by: AccountId,
+        pub reason_hash: String,
+    }
+
+    #[ink(event)]
+    pub struct StudentUnenrolled {
+        #[ink(topic)]
+        pub course_id: u32,
+        #[ink(topic)]
+        pub student: AccountId,
+        pub refunded: Balance,
+    }
+
+    #[ink(event)]
+    pub struct WaitlistJoined {
+        #[ink(topic)]
+        pub course_id: u32,
+        #[ink(topic)]
+        pub student: AccountId,
+        pub escrowed: Balance,
+    }
+
+    #[ink(event)]
+    pub struct WaitlistPromoted {
+        #[ink(topic)]
+        pub course_id: u32,
+        #[ink(topic)]
+        pub student: AccountId,
+    }
+
+    #[ink(event)]
+    pub struct SeatOffered {
+        #[ink(topic)]
+        pub course_id: u32,
+        #[ink(topic)]
+        pub student: AccountId,
+        pub deadline: Timestamp,
+    }
+
+    #[ink(event)]
+    pub struct WaitlistLeft {
+        #[ink(topic)]
+        pub course_id: u32,
+        #[ink(topic)]
+        pub student: AccountId,
+        pub refunded: Balance,
+    }
+
+    #[ink(event)]
+    pub struct RefundIssued {
+        #[ink(topic)]
+        pub course_id: u32,
+        #[ink(topic)]
+        pub student: AccountId,
+        pub amount: Balance,
+    }
+
+    #[ink(event)]
+    pub struct EscrowReleased {
+        #[ink(topic)]
+        pub course_id: u32,
+        pub amount: Balance,
+        pub platform_fee: Balance,
+    }
+
+    #[ink(event)]
+    pub struct RevenueDistributed {
+        #[ink(topic)]
+        pub course_id: u32,
+        pub token: Option<AccountId>,
+        pub shares: Vec<(AccountId, Balance)>,
+    }
+
+    #[ink(event)]
+    pub struct FundsWithdrawn {
+        #[ink(topic)]
+        pub account: AccountId,
+        pub token: Option<AccountId>,
+        pub amount: Balance,
+    }
+
+    #[ink(event)]
+    pub struct FeeUpdated {
+        pub old_fee_bps: u16,
+        pub new_fee_bps: u16,
+    }
+
+    #[ink(event)]
+    pub struct TreasuryWithdrawn {
+        #[ink(topic)]
+        pub to: AccountId,
+        pub token: Option<AccountId>,
+        pub amount: Balance,
+    }
+
+    #[ink(event)]
+    pub struct OwnershipTransferStarted {
+        #[ink(topic)]
+        pub owner: AccountId,
+        #[ink(topic)]
+        pub pending_owner: AccountId,
+    }
+
+    #[ink(event)]
+    pub struct OwnershipTransferred {
+        #[ink(topic)]
+        pub previous_owner: AccountId,
+        #[ink(topic)]
+        pub new_owner: AccountId,
+    }
+
+    #[ink(event)]
+    pub struct Paused {
+        #[ink(topic)]
+        pub by: AccountId,
+    }
+
+    #[ink(event)]
+    pub struct Unpaused {
+        #[ink(topic)]
+        pub by: AccountId,
+    }
+
+    #[ink(event)]
+    pub struct Upgraded {
+        pub code_hash: Hash,
+    }
+
+    #[ink(event)]
+    pub struct Migrated {
+        pub from_version: u16,
+        pub to_version: u16,
+    }
+
+    #[ink(event)]
+    pub struct PSP34Transfer {
+        #[ink(topic)]
+        pub from: Option<AccountId>,
+        #[ink(topic)]
+        pub to: Option<AccountId>,
+        #[ink(topic)]
+        pub id: Id,
+    }
+
+    #[ink(event)]
+    pub struct PSP34Approval {
+        #[ink(topic)]
+        pub owner: AccountId,
+        #[ink(topic)]
+        pub operator: AccountId,
+        #[ink(topic)]
+        pub id: Option<Id>,
+        pub approved: bool,
+    }
+
+    #[ink(event)]
+    pub struct PSP34AttributeSet {
+        #[ink(topic)]
+        pub id: Id,
+        pub key: Vec<u8>,
+        pub data: Vec<u8>,
+    }
+
+    ////////////////////////////////////
+    ////////// ERRORS /////////////////
+    //////////////////////////////////
+    #[derive(Debug, PartialEq, Eq)]
+    #[ink::scale_derive(Encode, Decode, TypeInfo)]
+    pub enum Error {
+        CourseNotActive,
+        NotOwner,
+        NotTeacher,
+        NotStudent,
+        CourseNotFound,
+        CourseInactive,
+        CourseFull,
+        CourseNotStarted,
+        CourseEnded,
+        AlreadyEnrolled,
+        NotEnrolled,
+        InsufficientPayment,
+        InvalidInput,
+        InvalidTime,
+        AlreadyCompleted,
+        CertificateNotFound,
+        TransferError,
+        PSP34Error(PSP34Error),
+        CourseIsFull,
+        CourseInProgress,
+        Unauthorized,
+        NFTMintingFailed,
+        PaymentFailed,
+        RefundPeriodEnded,
+        InvalidFeePercentage,
+        NoRefundAvailable,
+        RefundPeriodActive,
+        NothingToRelease,
+        InsufficientBalance,
+        CourseCancelled,
+        InvalidPaymentToken,
+        CertificateRevoked,
+        BatchTooLarge,
+        ContractPaused,
+        NotPaused,
+        RoleNotFound,
+        InvalidRevenueShares,
+        UpgradeFailed,
+        NothingToMigrate,
+        EnrollmentNotActive,
+        CourseNotFull,
+        AlreadyWaitlisted,
+        NotWaitlisted,
+        LessonNotFound,
+        LessonLocked,
+        LessonAlreadyCompleted,
+        AssignmentNotFound,
+        SubmissionNotFound,
+        AlreadyGraded,
+        NoCompletionRule,
+        CompletionRuleNotMet,
+        QuizNotFound,
+        QuizClosed,
+        QuizStillOpen,
+        AnswerKeyNotRevealed,
+        CommitmentMismatch,
+        NoCommitment,
+        AlreadyRevealed,
+        SessionNotFound,
+        SessionNotStarted,
+        MissingPrerequisite(u32),
+    }
+
+    #[ink(storage)]
+    pub struct Eduverse {
+        /// Layout version of the data below, see `migrate`
+        storage_version: u16,
+        /// Course counter for generating course IDs
+        course_counter: u32,
+        /// Mapping of course ID to course details
+        courses: Mapping<u32, Course>,
+        /// Next course ID to rewrite while a `migrate` run is in progress
+        migration_cursor: Lazy<u32>,
+        /// Version 1 list of a student's enrollments, only read by `migrate`
+        student_enrollments: Mapping<AccountId, Vec<u32>>,
+        /// Mapping of teacher address to their courses
+        teacher_courses: Mapping<AccountId, Vec<u32>>,
+        /// Version 1 list of a course's students, only read by `migrate`
+        course_students: Mapping<u32, Vec<AccountId>>,
+        /// Mapping of course ID to the number of lessons
+        lesson_count: Mapping<u32, u32>,
+        /// Mapping of course ID and lesson ID to the lesson
+        lessons: Mapping<(u32, u32), Lesson>,
+        /// Mapping of course ID, student and lesson ID to when the lesson was completed
+        lesson_completions: Mapping<(u32, AccountId, u32), Timestamp>,
+        /// Mapping of course ID and student to the number of lessons completed
+        lessons_completed: Mapping<(u32, AccountId), u32>,
+        /// Mapping of course ID to the number of assignments
+        assignment_count: Mapping<u32, u32>,
+        /// Mapping of course ID and assignment ID to the assignment
+        assignments: Mapping<(u32, u32), Assignment>,
+        /// Mapping of course ID, assignment ID and student to their submission
+        submissions: Mapping<(u32, u32, AccountId), Submission>,
+        /// Mapping of course ID to the number of quizzes
+        quiz_count: Mapping<u32, u32>,
+        /// Mapping of course ID and quiz ID to the quiz
+        quizzes: Mapping<(u32, u32), Quiz>,
+        /// Mapping of course ID, quiz ID and student to their committed answers
+        quiz_commitments: Mapping<(u32, u32, AccountId), Hash>,
+        /// Mapping of course ID, quiz ID and student to their revealed score
+        quiz_scores: Mapping<(u32, u32, AccountId), u32>,
+        /// Mapping of course ID and student to the points scored over all quizzes
+        quiz_points: Mapping<(u32, AccountId), u32>,
+        /// Mapping of course ID and quiz ID to when the answer key was revealed
+        quiz_key_revealed_at: Mapping<(u32, u32), Timestamp>,
+        /// Mapping of course ID to the number of live sessions
+        session_count: Mapping<u32, u32>,
+        /// Mapping of course ID and session ID to the session
+        sessions: Mapping<(u32, u32), Session>,
+        /// Mapping of course ID, session ID and student to when attendance was recorded
+        attendance: Mapping<(u32, u32, AccountId), Timestamp>,
+        /// Mapping of course ID and student to the number of sessions attended
+        attendance_count: Mapping<(u32, AccountId), u32>,
+        /// Accounts allowed to record attendance for every course
+        attendance_oracles: Mapping<AccountId, bool>,
+        /// Mapping of course ID and student to their place on the waitlist
+        waitlist_entries: Mapping<(u32, AccountId), WaitlistEntry>,
+        /// Mapping of course ID and waitlist position to the waiting student
+        waitlist_at: Mapping<(u32, u32), AccountId>,
+        /// Mapping of course ID to the next waitlist position to promote
+        waitlist_head: Mapping<u32, u32>,
+        /// Mapping of course ID to the next free waitlist position
+        waitlist_tail: Mapping<u32, u32>,
+        /// Mapping of course ID and student to the deadline of a seat offered to a
+        /// waitlisted student who pays on promotion, the seat is held until they enroll,
+        /// leave or the offer expires
+        seat_offers: Mapping<(u32, AccountId), Timestamp>,
+        /// Mapping of course ID to the number of seats held by open offers
+        offered_seats: Mapping<u32, u32>,
+        /// Mapping of course ID and offer position to the student and offer deadline
+        seat_offer_at: Mapping<(u32, u32), (AccountId, Timestamp)>,
+        /// Mapping of course ID to the next offer position to check for expiry
+        seat_offer_head: Mapping<u32, u32>,
+        /// Mapping of course ID to the next free offer position
+        seat_offer_tail: Mapping<u32, u32>,
+        /// Mapping of course ID and student to their latest enrollment, kept after leaving
+        enrollments: Mapping<(u32, AccountId), EnrollmentRecord>,
+        /// Mapping of course ID and student to the index positions of a current enrollment
+        enrollment_index: Mapping<(u32, AccountId), EnrollmentIndex>,
+        /// Mapping of course ID to the number of enrolled students
+        course_student_count: Mapping<u32, u32>,
+        /// Mapping of course ID and position to the enrolled student
+        course_student_at: Mapping<(u32, u32), AccountId>,
+        /// Mapping of student address to the number of courses enrolled in
+        student_course_count: Mapping<AccountId, u32>,
+        /// Mapping of student address and position to the course ID
+        student_course_at: Mapping<(AccountId, u32), u32>,
+        /// Mapping of course ID and student to completion status
+        course_completions: Mapping<(u32, AccountId), bool>,
+        /// Mapping of course ID and account to the role held in that course
+        course_roles: Mapping<(u32, AccountId), CourseRole>,
+        /// Mapping of course ID to accounts holding a role in it
+        course_role_members: Mapping<u32, Vec<AccountId>>,
+        /// Mapping of course ID and student to the payment held in escrow
+        escrowed_payments: Mapping<(u32, AccountId), Balance>,
+        /// Mapping of course ID to the total escrow not yet released to the teacher
+        course_escrow: Mapping<u32, Balance>,
+        /// Mapping of course ID to whether its escrow has been released to the payees
+        payments_released: Mapping<u32, bool>,
+        /// Mapping of account to released funds awaiting withdrawal
+        pending_withdrawals: Mapping<AccountId, Balance>,
+        /// Mapping of account and PSP22 token to released funds awaiting withdrawal
+        pending_token_withdrawals: Mapping<(AccountId, AccountId), Balance>,
+        /// Contract owner
+        owner: AccountId,
+        /// Account nominated to become owner, pending its acceptance
+        pending_owner: Option<AccountId>,
+        /// Whether course creation, enrollment and completion are halted
+        paused: bool,
+        /// Platform fee in basis points applied to newly created courses
+        platform_fee_bps: u16,
+        /// Accrued platform fees withdrawable by the owner
+        treasury: Balance,
+        /// Accrued platform fees per PSP22 token
+        token_treasury: Mapping<AccountId, Balance>,
+
+        // NFT functionality
+        data: PSP34Data,
+        certificate_info: Mapping<Id, CertificateInfo>,
+        // Certificate issued for each course ID and student
+        completion_certificates: Mapping<(u32, AccountId), Id>,
+        // Revoked certificates and why
+        certificate_revocations: Mapping<Id, RevocationRecord>,
+        // Custom metadata storage
+        attributes: Mapping<(psp34::Id, Vec<u8>), Vec<u8>>,
+        // Track certificates by student
+        student_certificates: Mapping<AccountId, Vec<psp34::Id>>,
+    }
+
+    impl Default for Eduverse {
+        fn default() -> Self {
+            Self::new()
+        }
     }
     ////////////////////////////////////
     ////////// CONTRACT METHODS ////////
@@ -186,22 +949,88 @@ mod eduverse {
     impl Eduverse {
         #[ink(constructor)]
         pub fn new() -> Self {
-            Self {
+            let mut instance = Self {
+                storage_version: STORAGE_VERSION,
                 course_counter: 0,
                 courses: Mapping::default(),
+                migration_cursor: Lazy::new(),
                 student_enrollments: Mapping::default(),
                 teacher_courses: Mapping::default(),
                 course_students: Mapping::default(),
+                lesson_count: Mapping::default(),
+                lessons: Mapping::default(),
+                lesson_completions: Mapping::default(),
+                lessons_completed: Mapping::default(),
+                assignment_count: Mapping::default(),
+                assignments: Mapping::default(),
+                submissions: Mapping::default(),
+                quiz_count: Mapping::default(),
+                quizzes: Mapping::default(),
+                quiz_commitments: Mapping::default(),
+                quiz_scores: Mapping::default(),
+                quiz_points: Mapping::default(),
+                quiz_key_revealed_at: Mapping::default(),
+                session_count: Mapping::default(),
+                sessions: Mapping::default(),
+                attendance: Mapping::default(),
+                attendance_count: Mapping::default(),
+                attendance_oracles: Mapping::default(),
+                waitlist_entries: Mapping::default(),
+                waitlist_at: Mapping::default(),
+                waitlist_head: Mapping::default(),
+                waitlist_tail: Mapping::default(),
+                seat_offers: Mapping::default(),
+                offered_seats: Mapping::default(),
+                seat_offer_at: Mapping::default(),
+                seat_offer_head: Mapping::default(),
+                seat_offer_tail: Mapping::default(),
+                enrollments: Mapping::default(),
+                enrollment_index: Mapping::default(),
+                course_student_count: Mapping::default(),
+                course_student_at: Mapping::default(),
+                student_course_count: Mapping::default(),
+                student_course_at: Mapping::default(),
                 course_completions: Mapping::default(),
+                course_roles: Mapping::default(),
+                course_role_members: Mapping::default(),
+                escrowed_payments: Mapping::default(),
+                course_escrow: Mapping::default(),
+                payments_released: Mapping::default(),
+                pending_withdrawals: Mapping::default(),
+                pending_token_withdrawals: Mapping::default(),
                 owner: Self::env().caller(),
+                pending_owner: None,
+                paused: false,
+                platform_fee_bps: 0,
+                treasury: 0,
+                token_treasury: Mapping::default(),
                 data: PSP34Data::new(),
                 certificate_info: Mapping::default(),
+                completion_certificates: Mapping::default(),
+                certificate_revocations: Mapping::default(),
                 attributes: Mapping::default(),
                 student_certificates: Mapping::default(),
-            }
+            };
+
+            // Collection level metadata lives under the collection id
+            let collection_id = Id::U8(0);
+            instance.set_attribute(
+                collection_id.clone(),
+                "name",
+                String::from(COLLECTION_NAME).into_bytes(),
+            );
+            instance.set_attribute(
+                collection_id,
+                "symbol",
+                String::from(COLLECTION_SYMBOL).into_bytes(),
+            );
+
+            instance
         }
 
+        // Every course field is a flat message argument so clients can call it directly
         #[ink(message)]
+        #[allow(clippy::too_many_arguments)]
         pub fn create_course(
             &mut self,
             title: String,
@@ -211,7 +1040,11 @@ mod eduverse {
             end_time: Timestamp,
             price: Balance,
             metadata_hash: String,
+            refund_window: Timestamp,
+            payment_token: Option<AccountId>,
+            prerequisites: Vec<u32>,
         ) -> Result<u32, Error> {
+            self.ensure_not_paused()?;
             let caller = self.env().caller();
             let current_time = self.env().block_timestamp();
 
@@ -226,8 +1059,20 @@ mod eduverse {
             }
 
             // Validate refund deadline if refundable
+            match start_time.checked_add(refund_window) {
+                Some(refund_deadline) if refund_deadline <= end_time => {}
+                _ => return Err(Error::InvalidTime),
+            }
+
+            // A token price must name a token contract other than this one
+            if let Some(token) = payment_token {
+                if token == self.env().account_id() || !self.env().is_contract(&token) {
+                    return Err(Error::InvalidPaymentToken);
+                }
+            }
 
             let course_id = self.course_counter;
+            self.validate_prerequisites(course_id, &prerequisites)?;
             self.course_counter = self
                 .course_counter
                 .checked_add(1)
@@ -246,6 +1091,13 @@ mod eduverse {
                 active: true,
                 metadata_hash,
                 created_at: current_time,
+                refund_window,
+                platform_fee_bps: self.platform_fee_bps,
+                cancelled: false,
+                payment_token,
+                payees: Vec::new(),
+                prerequisites,
+                completion_rule: None,
             };
 
             // Store course
@@ -266,7 +1118,9 @@ mod eduverse {
             Ok(course_id)
         }
 
+        // Takes the same flat arguments as `create_course`, `None` keeps the current value
         #[ink(message)]
+        #[allow(clippy::too_many_arguments)]
         pub fn update_course(
             &mut self,
             course_id: u32,
@@ -276,15 +1130,22 @@ mod eduverse {
             price: Option<Balance>,
             active: Option<bool>,
             metadata_hash: Option<String>,
+            prerequisites: Option<Vec<u32>>,
         ) -> Result<(), Error> {
+            self.ensure_not_paused()?;
             let caller = self.env().caller();
             let mut course = self.courses.get(course_id).ok_or(Error::CourseNotFound)?;
 
-            // Only teacher can update
-            if caller != course.teacher {
+            // Only teacher or co-teachers can update
+            if !self.can_update_course(&course, caller) {
                 return Err(Error::Unauthorized);
             }
 
+            // Cancelled courses cannot be reopened
+            if course.cancelled {
+                return Err(Error::CourseCancelled);
+            }
+
             // Cannot update if course has started
             let current_time = self.env().block_timestamp();
             if current_time >= course.start_time {
@@ -326,6 +1187,11 @@ mod eduverse {
                 course.metadata_hash = new_hash;
             }
 
+            if let Some(new_prerequisites) = prerequisites {
+                self.validate_prerequisites(course_id, &new_prerequisites)?;
+                course.prerequisites = new_prerequisites;
+            }
+
             // Save updated course
             self.courses.insert(course_id, &course);
 
@@ -335,829 +1201,6094 @@ mod eduverse {
                 teacher: caller,
             });
 
+            // A raised capacity goes to waiting students first
+            self.promote_waitlist(course_id);
+
             Ok(())
         }
 
-        #[ink(message, payable)]
-        pub fn enroll(&mut self, course_id: u32) -> Result<(), Error> {
+        #[ink(message)]
+        pub fn set_course_payees(
+            &mut self,
+            course_id: u32,
+            payees: Vec<Payee>,
+        ) -> Result<(), Error> {
+            self.ensure_not_paused()?;
             let caller = self.env().caller();
-            let course = self.courses.get(course_id).ok_or(Error::CourseNotFound)?;
-            let current_time = self.env().block_timestamp();
+            let mut course = self.courses.get(course_id).ok_or(Error::CourseNotFound)?;
 
-            // Validations
-            if !course.active {
-                return Err(Error::CourseNotActive);
+            // Only teacher can change who gets paid
+            if caller != course.teacher {
+                return Err(Error::Unauthorized);
             }
 
-            if current_time >= course.start_time {
+            // Cannot change the split once the course has started
+            if self.env().block_timestamp() >= course.start_time {
                 return Err(Error::CourseInProgress);
             }
 
-            if course.enrolled_count >= course.max_students {
-                return Err(Error::CourseIsFull);
-            }
-
-            if self.verify_enrollment(caller, course_id) {
-                return Err(Error::AlreadyEnrolled);
+            // An empty list hands all revenue back to the teacher
+            if !payees.is_empty() {
+                if payees.len() > MAX_PAYEES {
+                    return Err(Error::InvalidRevenueShares);
+                }
+                // Every payee appears once and receives a share
+                for (index, payee) in payees.iter().enumerate() {
+                    if payee.share_bps == 0
+                        || payees[..index]
+                            .iter()
+                            .any(|other| other.account == payee.account)
+                    {
+                        return Err(Error::InvalidRevenueShares);
+                    }
+                }
+                let total: Balance = payees
+                    .iter()
+                    .map(|payee| Balance::from(payee.share_bps))
+                    .sum();
+                if total != BPS_DENOMINATOR {
+                    return Err(Error::InvalidRevenueShares);
+                }
             }
 
-            if self.env().transferred_value() < course.price {
-                return Err(Error::InsufficientPayment);
-            }
-            // Transfer payment to teacher
-            if self.env().transfer(course.teacher, course.price).is_err() {
-                return Err(Error::PaymentFailed);
+            course.payees = payees;
+            self.courses.insert(course_id, &course);
+
+            self.env().emit_event(CourseUpdated {
+                course_id,
+                teacher: caller,
+            });
+
+            Ok(())
+        }
+
+        #[ink(message)]
+        pub fn set_completion_rule(
+            &mut self,
+            course_id: u32,
+            completion_rule: Option<CompletionRule>,
+        ) -> Result<(), Error> {
+            self.ensure_not_paused()?;
+            let caller = self.env().caller();
+            let mut course = self.courses.get(course_id).ok_or(Error::CourseNotFound)?;
+
+            // Only teacher or co-teachers can set completion criteria
+            if !self.can_update_course(&course, caller) {
+                return Err(Error::Unauthorized);
             }
 
-            // Update enrollments
-            let mut student_courses = self.student_enrollments.get(caller).unwrap_or_default();
-            student_courses.push(course_id);
-            self.student_enrollments.insert(caller, &student_courses);
+            if course.cancelled {
+                return Err(Error::CourseCancelled);
+            }
 
-            // Update course students
-            let mut course_students = self.course_students.get(course_id).unwrap_or_default();
-            course_students.push(caller);
-            self.course_students.insert(course_id, &course_students);
+            // A rule needs at least one criterion, an empty one would certify anyone
+            if let Some(rule) = completion_rule.as_ref() {
+                if rule.min_score.is_none() && !rule.all_lessons && rule.min_attendance.is_none() {
+                    return Err(Error::InvalidInput);
+                }
+                if rule
+                    .min_score
+                    .is_some_and(|min_score| Balance::from(min_score) > BPS_DENOMINATOR)
+                {
+                    return Err(Error::InvalidInput);
+                }
+            }
 
-            // Update course enrolled count
-            let mut updated_course = course.clone();
-            updated_course.enrolled_count = updated_course
-                .enrolled_count
-                .checked_add(1)
-                .unwrap_or(updated_course.enrolled_count);
-            self.courses.insert(course_id, &updated_course);
+            course.completion_rule = completion_rule;
+            self.courses.insert(course_id, &course);
 
-            // Emit event
-            self.env().emit_event(StudentEnrolled {
+            self.env().emit_event(CourseUpdated {
                 course_id,
-                student: caller,
-                payment: course.price,
+                teacher: caller,
             });
 
             Ok(())
         }
 
         #[ink(message)]
-        pub fn complete_course(&mut self, course_id: u32, student: AccountId) -> Result<(), Error> {
+        pub fn grant_role(
+            &mut self,
+            course_id: u32,
+            account: AccountId,
+            role: CourseRole,
+        ) -> Result<(), Error> {
+            self.ensure_not_paused()?;
             let caller = self.env().caller();
             let course = self.courses.get(course_id).ok_or(Error::CourseNotFound)?;
-            let current_time = self.env().block_timestamp();
 
-            // Only teacher can mark completion
+            // Only teacher can manage roles
             if caller != course.teacher {
                 return Err(Error::Unauthorized);
             }
 
-            // Check if student is enrolled
-            if !self.verify_enrollment(student, course_id) {
-                return Err(Error::NotEnrolled);
+            if account == course.teacher {
+                return Err(Error::InvalidInput);
             }
 
-            // Check if course has ended
-            if current_time < course.end_time {
-                return Err(Error::CourseInProgress);
+            // Granting a new role replaces any previous one
+            if !self.course_roles.contains((course_id, account)) {
+                let mut members = self.course_role_members.get(course_id).unwrap_or_default();
+                members.push(account);
+                self.course_role_members.insert(course_id, &members);
             }
+            self.course_roles.insert((course_id, account), &role);
 
-            // Mark as completed
-            self.course_completions.insert((course_id, student), &true);
+            self.env().emit_event(RoleGranted {
+                course_id,
+                account,
+                role,
+            });
 
-            // Generate a unique ID for the certificate NFT
-            // Use hash of student ID and course ID for uniqueness
-            use ink::env::hash;
+            Ok(())
+        }
 
-            let mut input = Vec::new();
-            input.extend_from_slice(&course_id.to_be_bytes());
-            input.extend_from_slice(student.as_ref());
-            input.extend_from_slice(&current_time.to_be_bytes());
+        #[ink(message)]
+        pub fn revoke_role(&mut self, course_id: u32, account: AccountId) -> Result<(), Error> {
+            self.ensure_not_paused()?;
+            let caller = self.env().caller();
+            let course = self.courses.get(course_id).ok_or(Error::CourseNotFound)?;
 
-            let mut certificate_hash = [0u8; 16]; // 16 bytes for blake2_128
-            hash::Blake2x128::hash(&input, &mut certificate_hash);
+            // Only teacher can manage roles
+            if caller != course.teacher {
+                return Err(Error::Unauthorized);
+            }
 
-            let certificate_id = psp34::Id::U128(u128::from_be_bytes(certificate_hash));
+            let role = self
+                .course_roles
+                .take((course_id, account))
+                .ok_or(Error::RoleNotFound)?;
 
-            // Create certificate info
-            let certificate = CertificateInfo {
+            let mut members = self.course_role_members.get(course_id).unwrap_or_default();
+            members.retain(|member| *member != account);
+            self.course_role_members.insert(course_id, &members);
+
+            self.env().emit_event(RoleRevoked {
                 course_id,
-                course_title: course.title.clone(),
-                student,
-                completion_date: current_time,
-            };
+                account,
+                role,
+            });
 
-            // Mint NFT certificate
-            match self.mint_certificate(student, certificate_id.clone(), certificate) {
-                Ok(_) => {
-                    // Emit completion event
-                    self.env().emit_event(CourseCompleted {
-                        course_id,
-                        student,
-                        certificate_id,
-                    });
-                    Ok(())
-                }
-                Err(_) => Err(Error::NFTMintingFailed),
-            }
+            Ok(())
         }
 
-        // Helper function to mint certificate
-        fn mint_certificate(
-            &mut self,
-            to: AccountId,
-            id: psp34::Id,
-            certificate: CertificateInfo,
-        ) -> Result<(), Error> {
-            // Mint the token
-            let events = self
-                .data
-                .mint(to, id.clone())
-                .map_err(|_| Error::NFTMintingFailed)?;
-
-            // Store certificate info
-            self.certificate_info.insert(id.clone(), &certificate);
-
-            // Store metadata attributes in our custom mapping
-            self.attributes.insert(
-                (id.clone(), String::from("course_title").into_bytes()),
-                &certificate.course_title.into_bytes(),
-            );
+        #[ink(message, payable)]
+        pub fn enroll(&mut self, course_id: u32) -> Result<(), Error> {
+            self.ensure_not_paused()?;
+            let caller = self.env().caller();
+            let course = self.courses.get(course_id).ok_or(Error::CourseNotFound)?;
 
-            self.attributes.insert(
-                (id.clone(), String::from("course_id").into_bytes()),
-                &certificate.course_id.to_string().into_bytes(),
-            );
+            self.ensure_can_enroll(&course, caller)?;
 
-            self.attributes.insert(
-                (id.clone(), String::from("completion_date").into_bytes()),
-                &certificate.completion_date.to_string().into_bytes(),
-            );
+            // Token priced courses are paid through `enroll_with_token`
+            if course.payment_token.is_some() {
+                return Err(Error::InvalidPaymentToken);
+            }
 
-            // Add to student's certificates
-            let mut student_certs = self.student_certificates.get(to).unwrap_or_default();
-            student_certs.push(id.clone());
-            self.student_certificates.insert(to, &student_certs);
+            let transferred = self.env().transferred_value();
+            if transferred < course.price {
+                return Err(Error::InsufficientPayment);
+            }
 
-            for event in events {
-                match event {
-                    psp34::PSP34Event::Transfer { from, to, id } => {
-                        self.env().emit_event(PSP34Transfer { from, to, id });
-                    }
-                    psp34::PSP34Event::Approval {
-                        owner,
-                        operator,
-                        id,
-                        approved,
-                    } => {
-                        self.env().emit_event(PSP34Approval {
-                            owner,
-                            operator,
-                            id,
-                            approved,
-                        });
-                    }
-                    psp34::PSP34Event::AttributeSet { id, key, data } => {
-                        self.env().emit_event(PSP34AttributeSet { id, key, data });
-                    }
-                }
+            // Overpayment is never kept, the student can withdraw it
+            let excess = transferred.saturating_sub(course.price);
+            if excess > 0 {
+                self.credit_withdrawal(caller, None, excess);
             }
 
+            self.record_enrollment(course, caller, excess);
+
             Ok(())
         }
 
-        ////////////////////////////////////
-        ////////// HELPER/VIEW FUNCTIONS ////
-        //////////////////////////////////
         #[ink(message)]
-        pub fn verify_enrollment(&self, student: AccountId, course_id: u32) -> bool {
-            if let Some(enrolled_students) = self.course_students.get(course_id) {
-                enrolled_students.contains(&student)
-            } else {
-                false
+        pub fn enroll_with_token(&mut self, course_id: u32) -> Result<(), Error> {
+            self.ensure_not_paused()?;
+            let caller = self.env().caller();
+            let course = self.courses.get(course_id).ok_or(Error::CourseNotFound)?;
+
+            self.ensure_can_enroll(&course, caller)?;
+
+            let token = course.payment_token.ok_or(Error::InvalidPaymentToken)?;
+
+            // Pull the price using the allowance the student granted beforehand
+            if course.price > 0 {
+                psp22::transfer_from(token, caller, self.env().account_id(), course.price)?;
             }
-        }
 
-        #[ink(message)]
-        pub fn verify_completion(&self, student: AccountId, course_id: u32) -> bool {
-            self.course_completions
-                .get((course_id, student))
-                .unwrap_or(false)
-        }
+            self.record_enrollment(course, caller, 0);
 
-        #[ink(message)]
-        pub fn get_course(&self, course_id: u32) -> Option<Course> {
-            self.courses.get(course_id)
+            Ok(())
         }
 
         #[ink(message)]
-        pub fn get_student_courses(&self, student: AccountId) -> Vec<u32> {
-            self.student_enrollments.get(student).unwrap_or_default()
-        }
-        // Add a method to get certificate attributes
-        #[ink(message)]
-        pub fn get_certificate_attribute(&self, id: psp34::Id, key: Vec<u8>) -> Option<Vec<u8>> {
-            self.attributes.get((id, key))
-        }
-        #[ink(message)]
-        pub fn get_student_certificate_vector(&self, student: AccountId) -> Vec<psp34::Id> {
-            self.student_certificates.get(student).unwrap_or_default()
-        }
+        pub fn unenroll(&mut self, course_id: u32) -> Result<(), Error> {
+            self.ensure_not_paused()?;
+            let caller = self.env().caller();
+            let course = self.courses.get(course_id).ok_or(Error::CourseNotFound)?;
+            let current_time = self.env().block_timestamp();
 
-        #[ink(message)]
-        pub fn get_teacher_courses(&self, teacher: AccountId) -> Vec<u32> {
-            self.teacher_courses.get(teacher).unwrap_or_default()
-        }
+            if !self.verify_enrollment(caller, course_id) {
+                return Err(Error::NotEnrolled);
+            }
 
-        #[ink(message)]
-        pub fn get_course_students(&self, course_id: u32) -> Vec<AccountId> {
-            self.course_students.get(course_id).unwrap_or_default()
-        }
+            if !self.is_enrollment_active(course_id, caller) {
+                return Err(Error::EnrollmentNotActive);
+            }
 
-        #[ink(message)]
-        pub fn get_courses(&self) -> Vec<Course> {
-            let mut courses_vec = Vec::new();
-            for course_id in 0..self.course_counter {
-                if let Some(course) = self.courses.get(course_id) {
-                    courses_vec.push(course);
-                }
+            // Seats can only be given up before the course starts
+            if current_time >= course.start_time {
+                return Err(Error::CourseInProgress);
             }
-            courses_vec
-        }
-        #[ink(message)]
-        pub fn verify_certificate(&self, id: psp34::Id) -> Result<CertificateInfo, Error> {
-            self.certificate_info.get(id).ok_or(Error::NFTMintingFailed)
-        }
-    }
-    ////////////////////////////////////
-    ////////// PSP34 TRAIT IMPLS ///////
-    //////////////////////////////////
-    impl psp34::PSP34 for Eduverse {
-        #[ink(message)]
-        fn collection_id(&self) -> Id {
-            Id::U8(0)
-        }
 
-        #[ink(message)]
-        fn balance_of(&self, owner: AccountId) -> u32 {
-            self.data.balance_of(owner)
-        }
+            // Leaving before the start is always inside the refund window
+            let refunded = self.take_escrow(course_id, caller);
+            let payment_token = course.payment_token;
+            self.remove_enrollment(course, caller, EnrollmentStatus::Dropped);
+            self.promote_waitlist(course_id);
 
-        #[ink(message)]
-        fn owner_of(&self, id: Id) -> Option<AccountId> {
-            self.data.owner_of(&id)
-        }
+            if refunded > 0 {
+                self.pay_out(caller, payment_token, refunded)?;
+            }
 
-        #[ink(message)]
-        fn allowance(&self, owner: AccountId, operator: AccountId, id: Option<Id>) -> bool {
-            self.data.allowance(owner, operator, id.as_ref())
-        }
+            self.env().emit_event(StudentUnenrolled {
+                course_id,
+                student: caller,
+                refunded,
+            });
 
-        #[ink(message)]
-        fn approve(
-            &mut self,
-            operator: AccountId,
-            id: Option<Id>,
-            approved: bool,
-        ) -> Result<(), PSP34Error> {
-            let _events = self
-                .data
-                .approve(self.env().caller(), operator, id, approved)?;
-            // Handle events
             Ok(())
         }
 
         #[ink(message)]
-        fn transfer(&mut self, _to: AccountId, _id: Id, _data: Vec<u8>) -> Result<(), PSP34Error> {
-            // Prevent transfers to maintain certificate integrity
-            return Err(PSP34Error::Custom(String::from(
-                "Certificates cannot be transferred",
-            )));
-        }
+        pub fn request_refund(&mut self, course_id: u32) -> Result<(), Error> {
+            self.ensure_not_paused()?;
+            let caller = self.env().caller();
+            let course = self.courses.get(course_id).ok_or(Error::CourseNotFound)?;
+            let current_time = self.env().block_timestamp();
 
-        #[ink(message)]
-        fn total_supply(&self) -> u128 {
-            self.data.total_supply()
-        }
+            if !self.verify_enrollment(caller, course_id) {
+                return Err(Error::NotEnrolled);
+            }
+
+            if !self.is_enrollment_active(course_id, caller) {
+                return Err(Error::EnrollmentNotActive);
+            }
+
+            // Refunds are only available until the end of the refund window, or at
+            // any time once the course was cancelled
+            if !course.cancelled && current_time >= self.refund_deadline(&course) {
+                return Err(Error::RefundPeriodEnded);
+            }
+
+            if self.get_escrowed_payment(course_id, caller) == 0 {
+                return Err(Error::NoRefundAvailable);
+            }
+
+            // Release the escrow and the seat before paying out
+            let amount = self.take_escrow(course_id, caller);
+            let payment_token = course.payment_token;
+            self.remove_enrollment(course, caller, EnrollmentStatus::Refunded);
+            self.promote_waitlist(course_id);
+
+            self.pay_out(caller, payment_token, amount)?;
+
+            self.env().emit_event(RefundIssued {
+                course_id,
+                student: caller,
+                amount,
+            });
+
+            Ok(())
+        }
+
+        #[ink(message, payable)]
+        pub fn join_waitlist(&mut self, course_id: u32, escrow: bool) -> Result<(), Error> {
+            self.ensure_not_paused()?;
+            let caller = self.env().caller();
+            let course = self.courses.get(course_id).ok_or(Error::CourseNotFound)?;
+            let current_time = self.env().block_timestamp();
+
+            // Validations
+            if !course.active {
+                return Err(Error::CourseNotActive);
+            }
+
+            if current_time >= course.start_time {
+                return Err(Error::CourseInProgress);
+            }
+
+            // Students with a free seat should enroll directly
+            if self.taken_seats(&course) < course.max_students {
+                return Err(Error::CourseNotFull);
+            }
+
+            if self.verify_enrollment(caller, course_id) {
+                return Err(Error::AlreadyEnrolled);
+            }
+
+            if self.waitlist_entries.contains((course_id, caller))
+                || self.seat_offers.contains((course_id, caller))
+            {
+                return Err(Error::AlreadyWaitlisted);
+            }
+
+            self.ensure_prerequisites(&course, caller)?;
+
+            // With `escrow` the price is held up front, so a freed seat can be handed
+            // over right away, without it the student is offered the seat and pays then
+            let escrowed = if escrow { course.price } else { 0 };
+            let transferred = self.env().transferred_value();
+            match course.payment_token {
+                Some(token) => {
+                    if transferred > 0 {
+                        return Err(Error::InvalidPaymentToken);
+                    }
+                    if escrowed > 0 {
+                        psp22::transfer_from(token, caller, self.env().account_id(), escrowed)?;
+                    }
+                }
+                None => {
+                    if transferred < escrowed {
+                        return Err(Error::InsufficientPayment);
+                    }
+                    let excess = transferred.saturating_sub(escrowed);
+                    if excess > 0 {
+                        self.credit_withdrawal(caller, None, excess);
+                    }
+                }
+            }
+
+            let position = self.waitlist_tail.get(course_id).unwrap_or(0);
+            self.waitlist_at.insert((course_id, position), &caller);
+            self.waitlist_tail
+                .insert(course_id, &position.saturating_add(1));
+            self.waitlist_entries.insert(
+                (course_id, caller),
+                &WaitlistEntry {
+                    position,
+                    escrowed,
+                    joined_at: current_time,
+                },
+            );
+
+            self.env().emit_event(WaitlistJoined {
+                course_id,
+                student: caller,
+                escrowed,
+            });
+
+            Ok(())
+        }
+
+        #[ink(message)]
+        pub fn leave_waitlist(&mut self, course_id: u32) -> Result<(), Error> {
+            self.ensure_not_paused()?;
+            let caller = self.env().caller();
+            let course = self.courses.get(course_id).ok_or(Error::CourseNotFound)?;
+
+            // Turning down an offered seat hands it to the next student in line
+            if self.take_seat_offer(course_id, caller) {
+                self.promote_waitlist(course_id);
+                self.env().emit_event(WaitlistLeft {
+                    course_id,
+                    student: caller,
+                    refunded: 0,
+                });
+                return Ok(());
+            }
+
+            // Students never promoted get their escrow back, also after the start or
+            // once the course was cancelled
+            let entry = self
+                .waitlist_entries
+                .take((course_id, caller))
+                .ok_or(Error::NotWaitlisted)?;
+            self.waitlist_at.remove((course_id, entry.position));
+
+            if entry.escrowed > 0 {
+                self.pay_out(caller, course.payment_token, entry.escrowed)?;
+            }
+
+            self.env().emit_event(WaitlistLeft {
+                course_id,
+                student: caller,
+                refunded: entry.escrowed,
+            });
+
+            Ok(())
+        }
+
+        #[ink(message)]
+        pub fn process_waitlist(&mut self, course_id: u32) -> Result<(), Error> {
+            self.ensure_not_paused()?;
+
+            // Anyone can continue a promotion stopped by `MAX_WAITLIST_STEPS` or take
+            // back the seats of expired offers
+            if !self.courses.contains(course_id) {
+                return Err(Error::CourseNotFound);
+            }
+            self.promote_waitlist(course_id);
+
+            Ok(())
+        }
+
+        #[ink(message)]
+        pub fn cancel_course(&mut self, course_id: u32, reason_hash: String) -> Result<(), Error> {
+            self.ensure_not_paused()?;
+            let caller = self.env().caller();
+            let mut course = self.courses.get(course_id).ok_or(Error::CourseNotFound)?;
+
+            // Teacher can cancel, owner can take down courses for moderation
+            if caller != course.teacher && caller != self.owner {
+                return Err(Error::Unauthorized);
+            }
+
+            if course.cancelled {
+                return Err(Error::CourseCancelled);
+            }
+
+            // Nothing is left to refund once the course ran or its escrow was paid out
+            if self.env().block_timestamp() >= course.end_time {
+                return Err(Error::CourseEnded);
+            }
+            if self.payments_released.get(course_id).unwrap_or(false) {
+                return Err(Error::RefundPeriodEnded);
+            }
+
+            // Enrolled students keep their escrow and claim it with `request_refund`, and
+            // waitlisted students with `leave_waitlist`, so cancelling costs the same
+            // however many students the course has. Open seat offers lapse with the course.
+            self.offered_seats.remove(course_id);
+
+            course.active = false;
+            course.cancelled = true;
+            self.courses.insert(course_id, &course);
+
+            self.env().emit_event(CourseCancelled {
+                course_id,
+                cancelled_by: caller,
+                reason_hash,
+            });
+
+            Ok(())
+        }
+
+        #[ink(message)]
+        pub fn release_payments(&mut self, course_id: u32) -> Result<(), Error> {
+            self.ensure_not_paused()?;
+            let course = self.courses.get(course_id).ok_or(Error::CourseNotFound)?;
+            let current_time = self.env().block_timestamp();
+
+            // Escrow of a cancelled course belongs to its students
+            if course.cancelled {
+                return Err(Error::CourseCancelled);
+            }
+
+            // Escrow stays locked while students can still request refunds
+            if current_time < self.refund_deadline(&course) {
+                return Err(Error::RefundPeriodActive);
+            }
+
+            let escrow = self.course_escrow.get(course_id).unwrap_or(0);
+            if escrow == 0 {
+                return Err(Error::NothingToRelease);
+            }
+            self.course_escrow.remove(course_id);
+            self.payments_released.insert(course_id, &true);
+
+            // Enrollment payments are escrowed until the refund window closes, so the
+            // platform fee and the payee credits are settled here instead of in `enroll`.
+            // Deduct the platform fee agreed at course creation
+            let platform_fee = escrow
+                .saturating_mul(Balance::from(course.platform_fee_bps))
+                .checked_div(BPS_DENOMINATOR)
+                .unwrap_or(0);
+            let amount = escrow.saturating_sub(platform_fee);
+            match course.payment_token {
+                None => self.treasury = self.treasury.saturating_add(platform_fee),
+                Some(token) => {
+                    let treasury = self.get_token_treasury_balance(token);
+                    self.token_treasury
+                        .insert(token, &treasury.saturating_add(platform_fee));
+                }
+            }
+
+            // Credit the payees, who pull the funds with `withdraw`
+            let shares = self.split_revenue(&course, amount);
+            for (account, share) in shares.iter() {
+                self.credit_withdrawal(*account, course.payment_token, *share);
+            }
+
+            self.env().emit_event(EscrowReleased {
+                course_id,
+                amount,
+                platform_fee,
+            });
+
+            self.env().emit_event(RevenueDistributed {
+                course_id,
+                token: course.payment_token,
+                shares,
+            });
+
+            Ok(())
+        }
+
+        #[ink(message)]
+        pub fn withdraw(&mut self, amount: Balance) -> Result<(), Error> {
+            self.withdraw_funds(None, amount)
+        }
+
+        #[ink(message)]
+        pub fn withdraw_token(&mut self, token: AccountId, amount: Balance) -> Result<(), Error> {
+            self.withdraw_funds(Some(token), amount)
+        }
+
+        #[ink(message)]
+        pub fn transfer_ownership(&mut self, new_owner: AccountId) -> Result<(), Error> {
+            self.ensure_owner()?;
+
+            // The nominee has to accept before ownership moves
+            self.pending_owner = Some(new_owner);
+
+            self.env().emit_event(OwnershipTransferStarted {
+                owner: self.owner,
+                pending_owner: new_owner,
+            });
+
+            Ok(())
+        }
+
+        #[ink(message)]
+        pub fn accept_ownership(&mut self) -> Result<(), Error> {
+            let caller = self.env().caller();
+            if self.pending_owner != Some(caller) {
+                return Err(Error::Unauthorized);
+            }
+
+            let previous_owner = self.owner;
+            self.owner = caller;
+            self.pending_owner = None;
+
+            self.env().emit_event(OwnershipTransferred {
+                previous_owner,
+                new_owner: caller,
+            });
+
+            Ok(())
+        }
+
+        #[ink(message)]
+        pub fn pause(&mut self) -> Result<(), Error> {
+            self.ensure_owner()?;
+
+            if self.paused {
+                return Err(Error::ContractPaused);
+            }
+            self.paused = true;
+
+            self.env().emit_event(Paused {
+                by: self.env().caller(),
+            });
+
+            Ok(())
+        }
+
+        #[ink(message)]
+        pub fn unpause(&mut self) -> Result<(), Error> {
+            self.ensure_owner()?;
+
+            if !self.paused {
+                return Err(Error::NotPaused);
+            }
+            self.paused = false;
+
+            self.env().emit_event(Unpaused {
+                by: self.env().caller(),
+            });
+
+            Ok(())
+        }
+
+        #[ink(message)]
+        pub fn upgrade(&mut self, code_hash: Hash) -> Result<(), Error> {
+            self.ensure_owner()?;
+
+            // Storage is kept, run `migrate` on the new code if the layout changed
+            self.env()
+                .set_code_hash(&code_hash)
+                .map_err(|_| Error::UpgradeFailed)?;
+
+            self.env().emit_event(Upgraded { code_hash });
+
+            Ok(())
+        }
+
+        #[ink(message)]
+        pub fn migrate(&mut self) -> Result<(), Error> {
+            self.ensure_owner()?;
+
+            // Storage is only rewritten while paused, when every course, enrollment and
+            // payment message is rejected and only the owner's admin messages still run
+            if !self.paused {
+                return Err(Error::NotPaused);
+            }
+
+            let from_version = self.storage_version;
+            if from_version >= STORAGE_VERSION {
+                return Err(Error::NothingToMigrate);
+            }
+
+            // Each call rewrites a batch of courses, the version is bumped
+            // one step once every course was brought to the next layout
+            let start = self.migration_cursor.get().unwrap_or(0);
+            let end = start
+                .saturating_add(MIGRATION_BATCH)
+                .min(self.course_counter);
+            for course_id in start..end {
+                self.migrate_course(from_version, course_id);
+            }
+
+            if end < self.course_counter {
+                self.migration_cursor.set(&end);
+                return Ok(());
+            }
+
+            let to_version = from_version.saturating_add(1);
+            self.migration_cursor.set(&0);
+            self.storage_version = to_version;
+
+            self.env().emit_event(Migrated {
+                from_version,
+                to_version,
+            });
+
+            Ok(())
+        }
+
+        #[ink(message)]
+        pub fn set_base_uri(&mut self, uri: String) -> Result<(), Error> {
+            self.ensure_owner()?;
+
+            self.set_attribute(Id::U8(0), "baseURI", uri.into_bytes());
+
+            Ok(())
+        }
+
+        #[ink(message)]
+        pub fn set_platform_fee(&mut self, fee_bps: u16) -> Result<(), Error> {
+            self.ensure_owner()?;
+
+            if fee_bps > MAX_PLATFORM_FEE_BPS {
+                return Err(Error::InvalidFeePercentage);
+            }
+
+            let old_fee_bps = self.platform_fee_bps;
+            self.platform_fee_bps = fee_bps;
+
+            self.env().emit_event(FeeUpdated {
+                old_fee_bps,
+                new_fee_bps: fee_bps,
+            });
+
+            Ok(())
+        }
+
+        #[ink(message)]
+        pub fn set_attendance_oracle(
+            &mut self,
+            oracle: AccountId,
+            enabled: bool,
+        ) -> Result<(), Error> {
+            self.ensure_owner()?;
+
+            if enabled {
+                self.attendance_oracles.insert(oracle, &true);
+            } else {
+                self.attendance_oracles.remove(oracle);
+            }
+
+            self.env()
+                .emit_event(AttendanceOracleUpdated { oracle, enabled });
+
+            Ok(())
+        }
+
+        #[ink(message)]
+        pub fn withdraw_treasury(&mut self, amount: Balance) -> Result<(), Error> {
+            self.withdraw_fees(None, amount)
+        }
+
+        #[ink(message)]
+        pub fn withdraw_token_treasury(
+            &mut self,
+            token: AccountId,
+            amount: Balance,
+        ) -> Result<(), Error> {
+            self.withdraw_fees(Some(token), amount)
+        }
+
+        #[ink(message)]
+        pub fn add_lesson(
+            &mut self,
+            course_id: u32,
+            module: u32,
+            title: String,
+            content_hash: String,
+            unlock_time: Option<Timestamp>,
+        ) -> Result<u32, Error> {
+            self.ensure_not_paused()?;
+            let caller = self.env().caller();
+            let course = self.courses.get(course_id).ok_or(Error::CourseNotFound)?;
+
+            // Syllabus is managed by the teacher and co-teachers
+            if !self.can_update_course(&course, caller) {
+                return Err(Error::Unauthorized);
+            }
+
+            if course.cancelled {
+                return Err(Error::CourseCancelled);
+            }
+
+            let lesson_id = self.lesson_count.get(course_id).unwrap_or(0);
+            if title.len() > 100 || lesson_id >= MAX_LESSONS {
+                return Err(Error::InvalidInput);
+            }
+
+            self.lessons.insert(
+                (course_id, lesson_id),
+                &Lesson {
+                    module,
+                    title,
+                    content_hash,
+                    unlock_time,
+                },
+            );
+            self.lesson_count
+                .insert(course_id, &lesson_id.saturating_add(1));
+
+            self.env().emit_event(LessonAdded {
+                course_id,
+                lesson_id,
+                module,
+            });
+
+            Ok(lesson_id)
+        }
+
+        #[ink(message)]
+        pub fn update_lesson(
+            &mut self,
+            course_id: u32,
+            lesson_id: u32,
+            title: Option<String>,
+            content_hash: Option<String>,
+            unlock_time: Option<Option<Timestamp>>,
+        ) -> Result<(), Error> {
+            self.ensure_not_paused()?;
+            let caller = self.env().caller();
+            let course = self.courses.get(course_id).ok_or(Error::CourseNotFound)?;
+
+            // Syllabus is managed by the teacher and co-teachers
+            if !self.can_update_course(&course, caller) {
+                return Err(Error::Unauthorized);
+            }
+
+            if course.cancelled {
+                return Err(Error::CourseCancelled);
+            }
+
+            let mut lesson = self
+                .lessons
+                .get((course_id, lesson_id))
+                .ok_or(Error::LessonNotFound)?;
+
+            if let Some(new_title) = title {
+                if new_title.len() > 100 {
+                    return Err(Error::InvalidInput);
+                }
+                lesson.title = new_title;
+            }
+
+            if let Some(new_hash) = content_hash {
+                lesson.content_hash = new_hash;
+            }
+
+            if let Some(new_unlock_time) = unlock_time {
+                lesson.unlock_time = new_unlock_time;
+            }
+
+            self.lessons.insert((course_id, lesson_id), &lesson);
+
+            self.env().emit_event(LessonUpdated {
+                course_id,
+                lesson_id,
+            });
+
+            Ok(())
+        }
+
+        #[ink(message)]
+        pub fn mark_lesson_complete(
+            &mut self,
+            course_id: u32,
+            lesson_id: u32,
+        ) -> Result<(), Error> {
+            self.ensure_not_paused()?;
+            let caller = self.env().caller();
+            let current_time = self.env().block_timestamp();
+
+            if !self.verify_enrollment(caller, course_id) {
+                return Err(Error::NotEnrolled);
+            }
+
+            // Progress is frozen once the student completed or failed
+            if !self.is_enrollment_active(course_id, caller) {
+                return Err(Error::EnrollmentNotActive);
+            }
+
+            let lesson = self
+                .lessons
+                .get((course_id, lesson_id))
+                .ok_or(Error::LessonNotFound)?;
+
+            if lesson
+                .unlock_time
+                .is_some_and(|unlock_time| current_time < unlock_time)
+            {
+                return Err(Error::LessonLocked);
+            }
+
+            if self
+                .lesson_completions
+                .contains((course_id, caller, lesson_id))
+            {
+                return Err(Error::LessonAlreadyCompleted);
+            }
+
+            self.lesson_completions
+                .insert((course_id, caller, lesson_id), &current_time);
+            let completed = self.lessons_completed.get((course_id, caller)).unwrap_or(0);
+            self.lessons_completed
+                .insert((course_id, caller), &completed.saturating_add(1));
+
+            self.env().emit_event(LessonCompleted {
+                course_id,
+                student: caller,
+                lesson_id,
+            });
+
+            Ok(())
+        }
+
+        #[ink(message)]
+        pub fn add_assignment(
+            &mut self,
+            course_id: u32,
+            title: String,
+            deadline: Timestamp,
+            max_score: u32,
+            weight: u32,
+        ) -> Result<u32, Error> {
+            self.ensure_not_paused()?;
+            let caller = self.env().caller();
+            let course = self.courses.get(course_id).ok_or(Error::CourseNotFound)?;
+
+            // Assignments are set by the teacher and co-teachers
+            if !self.can_update_course(&course, caller) {
+                return Err(Error::Unauthorized);
+            }
+
+            if course.cancelled {
+                return Err(Error::CourseCancelled);
+            }
+
+            let assignment_id = self.assignment_count.get(course_id).unwrap_or(0);
+            if title.len() > 100
+                || max_score == 0
+                || weight == 0
+                || assignment_id >= MAX_ASSIGNMENTS
+            {
+                return Err(Error::InvalidInput);
+            }
+
+            if deadline <= self.env().block_timestamp() {
+                return Err(Error::InvalidTime);
+            }
+
+            self.assignments.insert(
+                (course_id, assignment_id),
+                &Assignment {
+                    title,
+                    deadline,
+                    max_score,
+                    weight,
+                },
+            );
+            self.assignment_count
+                .insert(course_id, &assignment_id.saturating_add(1));
+
+            self.env().emit_event(AssignmentAdded {
+                course_id,
+                assignment_id,
+                deadline,
+            });
+
+            Ok(assignment_id)
+        }
+
+        #[ink(message)]
+        pub fn submit_assignment(
+            &mut self,
+            course_id: u32,
+            assignment_id: u32,
+            content_hash: String,
+        ) -> Result<(), Error> {
+            self.ensure_not_paused()?;
+            let caller = self.env().caller();
+            let current_time = self.env().block_timestamp();
+
+            if !self.verify_enrollment(caller, course_id) {
+                return Err(Error::NotEnrolled);
+            }
+
+            // Work is frozen once the student completed or failed the course
+            if !self.is_enrollment_active(course_id, caller) {
+                return Err(Error::EnrollmentNotActive);
+            }
+
+            let assignment = self
+                .assignments
+                .get((course_id, assignment_id))
+                .ok_or(Error::AssignmentNotFound)?;
+
+            // Work can be resubmitted until it has been graded
+            if self
+                .submissions
+                .get((course_id, assignment_id, caller))
+                .is_some_and(|submission| submission.score.is_some())
+            {
+                return Err(Error::AlreadyGraded);
+            }
+
+            let late = current_time > assignment.deadline;
+            self.submissions.insert(
+                (course_id, assignment_id, caller),
+                &Submission {
+                    content_hash: content_hash.clone(),
+                    submitted_at: current_time,
+                    late,
+                    score: None,
+                    graded_by: None,
+                },
+            );
+
+            self.env().emit_event(AssignmentSubmitted {
+                course_id,
+                student: caller,
+                assignment_id,
+                content_hash,
+                late,
+            });
+
+            Ok(())
+        }
+
+        #[ink(message)]
+        pub fn grade_submission(
+            &mut self,
+            course_id: u32,
+            assignment_id: u32,
+            student: AccountId,
+            score: u32,
+        ) -> Result<(), Error> {
+            self.ensure_not_paused()?;
+            let caller = self.env().caller();
+            let course = self.courses.get(course_id).ok_or(Error::CourseNotFound)?;
+
+            // Teacher, co-teachers, graders and teaching assistants can grade
+            if !self.can_grade_course(&course, caller) && !self.can_assist_course(&course, caller) {
+                return Err(Error::Unauthorized);
+            }
+
+            let assignment = self
+                .assignments
+                .get((course_id, assignment_id))
+                .ok_or(Error::AssignmentNotFound)?;
+            let mut submission = self
+                .submissions
+                .get((course_id, assignment_id, student))
+                .ok_or(Error::SubmissionNotFound)?;
+
+            if score > assignment.max_score {
+                return Err(Error::InvalidInput);
+            }
+
+            // Grades are final once the student completed or failed the course
+            if !self.is_enrollment_active(course_id, student) {
+                return Err(Error::EnrollmentNotActive);
+            }
+
+            // Grading again overwrites the previous score
+            submission.score = Some(score);
+            submission.graded_by = Some(caller);
+            self.submissions
+                .insert((course_id, assignment_id, student), &submission);
+
+            self.env().emit_event(SubmissionGraded {
+                course_id,
+                student,
+                assignment_id,
+                score,
+                graded_by: caller,
+            });
+
+            Ok(())
+        }
+
+        #[ink(message)]
+        pub fn create_quiz(
+            &mut self,
+            course_id: u32,
+            question_count: u32,
+            weight: u32,
+            key_commitment: Hash,
+            commit_deadline: Timestamp,
+            reveal_deadline: Timestamp,
+        ) -> Result<u32, Error> {
+            self.ensure_not_paused()?;
+            let caller = self.env().caller();
+            let course = self.courses.get(course_id).ok_or(Error::CourseNotFound)?;
+
+            // Quizzes are set by the teacher and co-teachers
+            if !self.can_update_course(&course, caller) {
+                return Err(Error::Unauthorized);
+            }
+
+            if course.cancelled {
+                return Err(Error::CourseCancelled);
+            }
+
+            let quiz_id = self.quiz_count.get(course_id).unwrap_or(0);
+            if question_count == 0
+                || question_count > MAX_QUIZ_QUESTIONS
+                || weight == 0
+                || quiz_id >= MAX_QUIZZES
+            {
+                return Err(Error::InvalidInput);
+            }
+
+            if commit_deadline <= self.env().block_timestamp() || reveal_deadline <= commit_deadline
+            {
+                return Err(Error::InvalidTime);
+            }
+
+            self.quizzes.insert(
+                (course_id, quiz_id),
+                &Quiz {
+                    question_count,
+                    weight,
+                    key_commitment,
+                    commit_deadline,
+                    reveal_deadline,
+                    answer_key: None,
+                },
+            );
+            self.quiz_count
+                .insert(course_id, &quiz_id.saturating_add(1));
+
+            self.env().emit_event(QuizCreated {
+                course_id,
+                quiz_id,
+                commit_deadline,
+                reveal_deadline,
+            });
+
+            Ok(quiz_id)
+        }
+
+        #[ink(message)]
+        pub fn commit_quiz_answers(
+            &mut self,
+            course_id: u32,
+            quiz_id: u32,
+            commitment: Hash,
+        ) -> Result<(), Error> {
+            self.ensure_not_paused()?;
+            let caller = self.env().caller();
+
+            if !self.verify_enrollment(caller, course_id) {
+                return Err(Error::NotEnrolled);
+            }
+
+            if !self.is_enrollment_active(course_id, caller) {
+                return Err(Error::EnrollmentNotActive);
+            }
+
+            let quiz = self
+                .quizzes
+                .get((course_id, quiz_id))
+                .ok_or(Error::QuizNotFound)?;
+
+            // Commitments can be replaced until the deadline
+            if self.env().block_timestamp() >= quiz.commit_deadline {
+                return Err(Error::QuizClosed);
+            }
+
+            self.quiz_commitments
+                .insert((course_id, quiz_id, caller), &commitment);
+
+            self.env().emit_event(QuizAnswersCommitted {
+                course_id,
+                student: caller,
+                quiz_id,
+            });
+
+            Ok(())
+        }
+
+        #[ink(message)]
+        pub fn reveal_answer_key(
+            &mut self,
+            course_id: u32,
+            quiz_id: u32,
+            answers: Vec<u8>,
+            salt: [u8; 32],
+        ) -> Result<(), Error> {
+            self.ensure_not_paused()?;
+            let caller = self.env().caller();
+            let course = self.courses.get(course_id).ok_or(Error::CourseNotFound)?;
+            let current_time = self.env().block_timestamp();
+
+            if !self.can_update_course(&course, caller) {
+                return Err(Error::Unauthorized);
+            }
+
+            let mut quiz = self
+                .quizzes
+                .get((course_id, quiz_id))
+                .ok_or(Error::QuizNotFound)?;
+
+            // The key only becomes public once no student can commit anymore
+            if current_time < quiz.commit_deadline {
+                return Err(Error::QuizStillOpen);
+            }
+
+            if current_time >= quiz.reveal_deadline {
+                return Err(Error::QuizClosed);
+            }
+
+            if quiz.answer_key.is_some() {
+                return Err(Error::AlreadyRevealed);
+            }
+
+            if Self::quiz_commitment(course_id, quiz_id, None, &answers, &salt)
+                != quiz.key_commitment
+                || answers.len() != quiz.question_count as usize
+            {
+                return Err(Error::CommitmentMismatch);
+            }
+
+            quiz.answer_key = Some(answers);
+            self.quizzes.insert((course_id, quiz_id), &quiz);
+            self.quiz_key_revealed_at
+                .insert((course_id, quiz_id), &current_time);
+
+            self.env()
+                .emit_event(QuizKeyRevealed { course_id, quiz_id });
+
+            Ok(())
+        }
+
+        #[ink(message)]
+        pub fn reveal_quiz_answers(
+            &mut self,
+            course_id: u32,
+            quiz_id: u32,
+            answers: Vec<u8>,
+            salt: [u8; 32],
+        ) -> Result<u32, Error> {
+            self.ensure_not_paused()?;
+            let caller = self.env().caller();
+
+            if !self.verify_enrollment(caller, course_id) {
+                return Err(Error::NotEnrolled);
+            }
+
+            if !self.is_enrollment_active(course_id, caller) {
+                return Err(Error::EnrollmentNotActive);
+            }
+
+            let quiz = self
+                .quizzes
+                .get((course_id, quiz_id))
+                .ok_or(Error::QuizNotFound)?;
+            let answer_key = quiz.answer_key.ok_or(Error::AnswerKeyNotRevealed)?;
+
+            // Students get their own window to reveal, which opens with the answer key
+            let revealed_at = self
+                .quiz_key_revealed_at
+                .get((course_id, quiz_id))
+                .unwrap_or(quiz.reveal_deadline);
+            if self.env().block_timestamp() >= revealed_at.saturating_add(QUIZ_REVEAL_WINDOW) {
+                return Err(Error::QuizClosed);
+            }
+
+            if self.quiz_scores.contains((course_id, quiz_id, caller)) {
+                return Err(Error::AlreadyRevealed);
+            }
+
+            if answers.len() != quiz.question_count as usize {
+                return Err(Error::InvalidInput);
+            }
+
+            // The commitment binds the answers to the student, so copying
+            // someone else's commitment cannot be revealed
+            let commitment = self
+                .quiz_commitments
+                .get((course_id, quiz_id, caller))
+                .ok_or(Error::NoCommitment)?;
+            if Self::quiz_commitment(course_id, quiz_id, Some(caller), &answers, &salt)
+                != commitment
+            {
+                return Err(Error::CommitmentMismatch);
+            }
+
+            let score = answers
+                .iter()
+                .zip(answer_key.iter())
+                .filter(|(answer, key)| answer == key)
+                .count();
+            let score = u32::try_from(score).unwrap_or(u32::MAX);
+
+            self.quiz_scores
+                .insert((course_id, quiz_id, caller), &score);
+            let points = self.quiz_points.get((course_id, caller)).unwrap_or(0);
+            self.quiz_points
+                .insert((course_id, caller), &points.saturating_add(score));
+
+            self.env().emit_event(QuizScored {
+                course_id,
+                student: caller,
+                quiz_id,
+                score,
+            });
+
+            Ok(score)
+        }
+
+        /// Commitment to quiz answers: blake2x256 over the course ID and quiz ID
+        /// (big endian), the student's account (left out for the answer key),
+        /// the answer bytes and the salt
+        pub fn quiz_commitment(
+            course_id: u32,
+            quiz_id: u32,
+            student: Option<AccountId>,
+            answers: &[u8],
+            salt: &[u8; 32],
+        ) -> Hash {
+            use ink::env::hash;
+
+            let mut input = Vec::new();
+            input.extend_from_slice(&course_id.to_be_bytes());
+            input.extend_from_slice(&quiz_id.to_be_bytes());
+            if let Some(student) = student {
+                input.extend_from_slice(student.as_ref());
+            }
+            input.extend_from_slice(answers);
+            input.extend_from_slice(salt);
+
+            let mut commitment = [0u8; 32];
+            hash::Blake2x256::hash(&input, &mut commitment);
+
+            Hash::from(commitment)
+        }
+
+        #[ink(message)]
+        pub fn add_session(
+            &mut self,
+            course_id: u32,
+            title: String,
+            starts_at: Timestamp,
+        ) -> Result<u32, Error> {
+            self.ensure_not_paused()?;
+            let caller = self.env().caller();
+            let course = self.courses.get(course_id).ok_or(Error::CourseNotFound)?;
+
+            // Sessions are scheduled by the teacher and co-teachers
+            if !self.can_update_course(&course, caller) {
+                return Err(Error::Unauthorized);
+            }
+
+            if course.cancelled {
+                return Err(Error::CourseCancelled);
+            }
+
+            // Sessions take place while the course runs
+            if starts_at < course.start_time || starts_at >= course.end_time {
+                return Err(Error::InvalidTime);
+            }
+
+            let session_id = self.session_count.get(course_id).unwrap_or(0);
+            if title.len() > 100 || session_id >= MAX_SESSIONS {
+                return Err(Error::InvalidInput);
+            }
+
+            self.sessions
+                .insert((course_id, session_id), &Session { title, starts_at });
+            self.session_count
+                .insert(course_id, &session_id.saturating_add(1));
+
+            self.env().emit_event(SessionAdded {
+                course_id,
+                session_id,
+                starts_at,
+            });
+
+            Ok(session_id)
+        }
+
+        #[ink(message)]
+        pub fn record_attendance(
+            &mut self,
+            course_id: u32,
+            session_id: u32,
+            students: Vec<AccountId>,
+        ) -> Result<u32, Error> {
+            self.ensure_not_paused()?;
+            let caller = self.env().caller();
+            let course = self.courses.get(course_id).ok_or(Error::CourseNotFound)?;
+            let current_time = self.env().block_timestamp();
+
+            // Teacher, co-teachers, assistants or a registered oracle reporting from the
+            // classroom rooms
+            if !self.can_assist_course(&course, caller) && !self.attendance_oracles.contains(caller)
+            {
+                return Err(Error::Unauthorized);
+            }
+
+            // Bound the batch to stay within block weight
+            if students.is_empty() {
+                return Err(Error::InvalidInput);
+            }
+            if students.len() > MAX_ATTENDANCE_BATCH {
+                return Err(Error::BatchTooLarge);
+            }
+
+            let session = self
+                .sessions
+                .get((course_id, session_id))
+                .ok_or(Error::SessionNotFound)?;
+            if current_time < session.starts_at {
+                return Err(Error::SessionNotStarted);
+            }
+
+            // Students not enrolled or already recorded are skipped
+            let mut recorded = Vec::new();
+            for student in students {
+                if !self.verify_enrollment(student, course_id)
+                    || self.attendance.contains((course_id, session_id, student))
+                {
+                    continue;
+                }
+
+                self.attendance
+                    .insert((course_id, session_id, student), &current_time);
+                let attended = self.attendance_count.get((course_id, student)).unwrap_or(0);
+                self.attendance_count
+                    .insert((course_id, student), &attended.saturating_add(1));
+                recorded.push(student);
+            }
+
+            let count = u32::try_from(recorded.len()).unwrap_or(u32::MAX);
+            self.env().emit_event(AttendanceRecorded {
+                course_id,
+                session_id,
+                recorded_by: caller,
+                students: recorded,
+            });
+
+            Ok(count)
+        }
+
+        #[ink(message)]
+        pub fn complete_course(&mut self, course_id: u32, student: AccountId) -> Result<(), Error> {
+            self.ensure_not_paused()?;
+            let caller = self.env().caller();
+            let course = self.courses.get(course_id).ok_or(Error::CourseNotFound)?;
+            let current_time = self.env().block_timestamp();
+
+            // Only teacher, co-teachers or graders can mark completion
+            if !self.can_grade_course(&course, caller) {
+                return Err(Error::Unauthorized);
+            }
+
+            // Check if student is enrolled
+            if !self.verify_enrollment(student, course_id) {
+                return Err(Error::NotEnrolled);
+            }
+
+            // Check if course has ended
+            if current_time < course.end_time {
+                return Err(Error::CourseInProgress);
+            }
+
+            // A student earns at most one certificate per course
+            if self.is_completed(course_id, student) {
+                return Err(Error::AlreadyCompleted);
+            }
+
+            if !self.is_enrollment_active(course_id, student) {
+                return Err(Error::EnrollmentNotActive);
+            }
+
+            self.issue_certificate(&course, student)?;
+
+            Ok(())
+        }
+
+        #[ink(message)]
+        pub fn fail_student(&mut self, course_id: u32, student: AccountId) -> Result<(), Error> {
+            self.ensure_not_paused()?;
+            let caller = self.env().caller();
+            let course = self.courses.get(course_id).ok_or(Error::CourseNotFound)?;
+            let current_time = self.env().block_timestamp();
+
+            // Same graders who could have completed the student
+            if !self.can_grade_course(&course, caller) {
+                return Err(Error::Unauthorized);
+            }
+
+            if !self.verify_enrollment(student, course_id) {
+                return Err(Error::NotEnrolled);
+            }
+
+            if current_time < course.end_time {
+                return Err(Error::CourseInProgress);
+            }
+
+            if course.cancelled {
+                return Err(Error::CourseCancelled);
+            }
+
+            if !self.is_enrollment_active(course_id, student) {
+                return Err(Error::EnrollmentNotActive);
+            }
+
+            self.set_enrollment_status(course_id, student, EnrollmentStatus::Failed);
+
+            self.env().emit_event(StudentFailed {
+                course_id,
+                student,
+                failed_by: caller,
+            });
+
+            Ok(())
+        }
+
+        #[ink(message)]
+        pub fn claim_certificate(&mut self, course_id: u32) -> Result<psp34::Id, Error> {
+            self.ensure_not_paused()?;
+            let caller = self.env().caller();
+            let course = self.courses.get(course_id).ok_or(Error::CourseNotFound)?;
+
+            if !self.verify_enrollment(caller, course_id) {
+                return Err(Error::NotEnrolled);
+            }
+
+            // Students can only complete themselves against the course's own rule
+            let rule = course
+                .completion_rule
+                .as_ref()
+                .ok_or(Error::NoCompletionRule)?;
+
+            // Certificates are claimed once the course is over, like `complete_course`
+            if self.env().block_timestamp() < course.end_time {
+                return Err(Error::CourseInProgress);
+            }
+
+            if self.is_completed(course_id, caller) {
+                return Err(Error::AlreadyCompleted);
+            }
+
+            if !self.is_enrollment_active(course_id, caller) {
+                return Err(Error::EnrollmentNotActive);
+            }
+
+            if !self.meets_completion_rule(course_id, rule, caller) {
+                return Err(Error::CompletionRuleNotMet);
+            }
+
+            self.issue_certificate(&course, caller)
+        }
+
+        // Helper function to check a student against a course's completion rule
+        fn meets_completion_rule(
+            &self,
+            course_id: u32,
+            rule: &CompletionRule,
+            student: AccountId,
+        ) -> bool {
+            if rule
+                .min_score
+                .is_some_and(|min_score| self.get_aggregate_score(course_id, student) < min_score)
+            {
+                return false;
+            }
+
+            if rule.min_attendance.is_some_and(|min_attendance| {
+                self.get_attendance_count(course_id, student) < min_attendance
+            }) {
+                return false;
+            }
+
+            // A course without lessons never meets a rule asking for all of them
+            let lessons = self.lesson_count.get(course_id).unwrap_or(0);
+            let completed = self
+                .lessons_completed
+                .get((course_id, student))
+                .unwrap_or(0);
+            !(rule.all_lessons && (lessons == 0 || completed < lessons))
+        }
+
+        #[ink(message)]
+        pub fn complete_course_batch(
+            &mut self,
+            course_id: u32,
+            students: Vec<AccountId>,
+        ) -> Result<Vec<CompletionResult>, Error> {
+            self.ensure_not_paused()?;
+            let caller = self.env().caller();
+            let course = self.courses.get(course_id).ok_or(Error::CourseNotFound)?;
+            let current_time = self.env().block_timestamp();
+
+            // Only teacher, co-teachers or graders can mark completion
+            if !self.can_grade_course(&course, caller) {
+                return Err(Error::Unauthorized);
+            }
+
+            // Bound the batch to stay within block weight
+            if students.is_empty() {
+                return Err(Error::InvalidInput);
+            }
+            if students.len() > MAX_COMPLETION_BATCH {
+                return Err(Error::BatchTooLarge);
+            }
+
+            // Check if course has ended
+            if current_time < course.end_time {
+                return Err(Error::CourseInProgress);
+            }
+
+            // Results are returned in the same order as `students`
+            let mut results = Vec::with_capacity(students.len());
+            for student in students {
+                let result = if !self.verify_enrollment(student, course_id) {
+                    CompletionResult::NotEnrolled
+                } else if self.is_completed(course_id, student) {
+                    CompletionResult::AlreadyCompleted
+                } else if !self.is_enrollment_active(course_id, student) {
+                    CompletionResult::NotActive
+                } else {
+                    CompletionResult::Completed(self.issue_certificate(&course, student)?)
+                };
+                results.push(result);
+            }
+
+            Ok(results)
+        }
+
+        // Helper function to record a completion and mint its certificate
+        fn issue_certificate(
+            &mut self,
+            course: &Course,
+            student: AccountId,
+        ) -> Result<psp34::Id, Error> {
+            let course_id = course.id;
+            let current_time = self.env().block_timestamp();
+
+            // Students of a cancelled course are owed a refund rather than a certificate
+            if course.cancelled {
+                return Err(Error::CourseCancelled);
+            }
+
+            // Mark as completed
+            self.course_completions.insert((course_id, student), &true);
+            self.set_enrollment_status(course_id, student, EnrollmentStatus::Completed);
+
+            let certificate_id = self.certificate_id_for(course_id, student);
+            self.completion_certificates
+                .insert((course_id, student), &certificate_id);
+
+            // Create certificate info
+            let certificate = CertificateInfo {
+                course_id,
+                course_title: course.title.clone(),
+                student,
+                completion_date: current_time,
+            };
+
+            // Mint NFT certificate
+            match self.mint_certificate(student, certificate_id.clone(), certificate) {
+                Ok(_) => {
+                    // Emit completion event
+                    self.env().emit_event(CourseCompleted {
+                        course_id,
+                        student,
+                        certificate_id: certificate_id.clone(),
+                    });
+                    Ok(certificate_id)
+                }
+                Err(_) => Err(Error::NFTMintingFailed),
+            }
+        }
+
+        #[ink(message)]
+        pub fn revoke_certificate(
+            &mut self,
+            id: psp34::Id,
+            reason_hash: String,
+        ) -> Result<(), Error> {
+            self.ensure_not_paused()?;
+            let caller = self.env().caller();
+            let certificate = self
+                .certificate_info
+                .get(id.clone())
+                .ok_or(Error::CertificateNotFound)?;
+            let course = self
+                .courses
+                .get(certificate.course_id)
+                .ok_or(Error::CourseNotFound)?;
+
+            // Issuing teacher can revoke, owner can revoke for moderation
+            if caller != course.teacher && caller != self.owner {
+                return Err(Error::Unauthorized);
+            }
+
+            if self.certificate_revocations.contains(id.clone()) {
+                return Err(Error::CertificateRevoked);
+            }
+
+            let record = RevocationRecord {
+                revoked_by: caller,
+                reason_hash: reason_hash.clone(),
+                revoked_at: self.env().block_timestamp(),
+            };
+            self.certificate_revocations.insert(id.clone(), &record);
+
+            // Surface the revocation to wallets reading the token metadata
+            self.set_attribute(id.clone(), "revoked", reason_hash.clone().into_bytes());
+
+            self.env().emit_event(CertificateRevoked {
+                certificate_id: id,
+                course_id: certificate.course_id,
+                student: certificate.student,
+                revoked_by: caller,
+                reason_hash,
+            });
+
+            Ok(())
+        }
+
+        // Helper function to derive the certificate NFT id for a completion
+        // Hash of course ID and student ID, so each pair maps to exactly one token
+        fn certificate_id_for(&self, course_id: u32, student: AccountId) -> psp34::Id {
+            use ink::env::hash;
+
+            let mut input = Vec::new();
+            input.extend_from_slice(&course_id.to_be_bytes());
+            input.extend_from_slice(student.as_ref());
+
+            let mut certificate_hash = [0u8; 16]; // 16 bytes for blake2_128
+            hash::Blake2x128::hash(&input, &mut certificate_hash);
+
+            psp34::Id::U128(u128::from_be_bytes(certificate_hash))
+        }
+
+        // Helper function to check who may edit a course
+        fn can_update_course(&self, course: &Course, account: AccountId) -> bool {
+            account == course.teacher
+                || matches!(
+                    self.course_roles.get((course.id, account)),
+                    Some(CourseRole::CoTeacher)
+                )
+        }
+
+        // Helper function to check who may help run a course's sessions and grade its work
+        fn can_assist_course(&self, course: &Course, account: AccountId) -> bool {
+            account == course.teacher
+                || matches!(
+                    self.course_roles.get((course.id, account)),
+                    Some(CourseRole::CoTeacher | CourseRole::TeachingAssistant)
+                )
+        }
+
+        // Helper function to check who may assess students of a course
+        fn can_grade_course(&self, course: &Course, account: AccountId) -> bool {
+            account == course.teacher
+                || matches!(
+                    self.course_roles.get((course.id, account)),
+                    Some(CourseRole::CoTeacher | CourseRole::Grader)
+                )
+        }
+
+        // Helper function to restrict admin messages to the owner
+        fn ensure_owner(&self) -> Result<(), Error> {
+            if self.env().caller() != self.owner {
+                return Err(Error::NotOwner);
+            }
+            Ok(())
+        }
+
+        // Helper function to reject state changing messages while paused
+        fn ensure_not_paused(&self) -> Result<(), Error> {
+            if self.paused {
+                return Err(Error::ContractPaused);
+            }
+            Ok(())
+        }
+
+        // Helper function to mint certificate
+        fn mint_certificate(
+            &mut self,
+            to: AccountId,
+            id: psp34::Id,
+            certificate: CertificateInfo,
+        ) -> Result<(), Error> {
+            // Mint the token
+            let events = self
+                .data
+                .mint(to, id.clone())
+                .map_err(|_| Error::NFTMintingFailed)?;
+
+            for event in events {
+                match event {
+                    psp34::PSP34Event::Transfer { from, to, id } => {
+                        self.env().emit_event(PSP34Transfer { from, to, id });
+                    }
+                    psp34::PSP34Event::Approval {
+                        owner,
+                        operator,
+                        id,
+                        approved,
+                    } => {
+                        self.env().emit_event(PSP34Approval {
+                            owner,
+                            operator,
+                            id,
+                            approved,
+                        });
+                    }
+                    psp34::PSP34Event::AttributeSet { id, key, data } => {
+                        self.env().emit_event(PSP34AttributeSet { id, key, data });
+                    }
+                }
+            }
+
+            // Store certificate info
+            self.certificate_info.insert(id.clone(), &certificate);
+
+            // Store metadata attributes readable through PSP34Metadata
+            self.set_attribute(
+                id.clone(),
+                "course_title",
+                certificate.course_title.into_bytes(),
+            );
+            self.set_attribute(
+                id.clone(),
+                "course_id",
+                certificate.course_id.to_string().into_bytes(),
+            );
+            self.set_attribute(
+                id.clone(),
+                "completion_date",
+                certificate.completion_date.to_string().into_bytes(),
+            );
+
+            // Add to student's certificates
+            let mut student_certs = self.student_certificates.get(to).unwrap_or_default();
+            student_certs.push(id);
+            self.student_certificates.insert(to, &student_certs);
+
+            Ok(())
+        }
+
+        // Helper function to store a token attribute and announce it to indexers
+        fn set_attribute(&mut self, id: Id, key: &str, data: Vec<u8>) {
+            let key = String::from(key).into_bytes();
+            self.attributes.insert((id.clone(), key.clone()), &data);
+            self.env().emit_event(PSP34AttributeSet { id, key, data });
+        }
+
+        // Helper function to check that a student may take a seat in the course
+        fn ensure_can_enroll(&self, course: &Course, student: AccountId) -> Result<(), Error> {
+            let current_time = self.env().block_timestamp();
+
+            // Validations
+            if !course.active {
+                return Err(Error::CourseNotActive);
+            }
+
+            if current_time >= course.start_time {
+                return Err(Error::CourseInProgress);
+            }
+
+            // A seat offered from the waitlist is held for that student
+            if self.get_seat_offer(course.id, student).is_none()
+                && self.taken_seats(course) >= course.max_students
+            {
+                return Err(Error::CourseIsFull);
+            }
+
+            if self.verify_enrollment(student, course.id) {
+                return Err(Error::AlreadyEnrolled);
+            }
+
+            self.ensure_prerequisites(course, student)
+        }
+
+        // Helper function to check a student holds a valid completion of every prerequisite
+        fn ensure_prerequisites(&self, course: &Course, student: AccountId) -> Result<(), Error> {
+            for prerequisite in course.prerequisites.iter().copied() {
+                if !self.verify_completion(student, prerequisite) {
+                    return Err(Error::MissingPrerequisite(prerequisite));
+                }
+            }
+
+            Ok(())
+        }
+
+        // Helper function to validate prerequisites, which must be distinct earlier courses
+        fn validate_prerequisites(
+            &self,
+            course_id: u32,
+            prerequisites: &[u32],
+        ) -> Result<(), Error> {
+            if prerequisites.len() > MAX_PREREQUISITES {
+                return Err(Error::InvalidInput);
+            }
+
+            // Only pointing at older courses keeps the curriculum free of cycles
+            for (index, prerequisite) in prerequisites.iter().enumerate() {
+                if *prerequisite >= course_id || prerequisites[..index].contains(prerequisite) {
+                    return Err(Error::InvalidInput);
+                }
+            }
+
+            Ok(())
+        }
+
+        // Helper function to add a paid-up student to the course
+        fn record_enrollment(&mut self, mut course: Course, student: AccountId, excess: Balance) {
+            let course_id = course.id;
+            self.take_seat_offer(course_id, student);
+
+            // Hold payment in escrow until the refund window closes
+            if course.price > 0 {
+                self.escrowed_payments
+                    .insert((course_id, student), &course.price);
+                let course_escrow = self.course_escrow.get(course_id).unwrap_or(0);
+                self.course_escrow
+                    .insert(course_id, &course_escrow.saturating_add(course.price));
+            }
+
+            // Update enrollments
+            self.index_enrollment(course_id, student);
+            self.enrollments.insert(
+                (course_id, student),
+                &EnrollmentRecord {
+                    enrolled_at: self.env().block_timestamp(),
+                    amount_paid: course.price,
+                    payment_asset: course.payment_token,
+                    status: EnrollmentStatus::Active,
+                },
+            );
+
+            // Update course enrolled count
+            course.enrolled_count = course
+                .enrolled_count
+                .checked_add(1)
+                .unwrap_or(course.enrolled_count);
+            self.courses.insert(course_id, &course);
+
+            // Emit event
+            self.env().emit_event(StudentEnrolled {
+                course_id,
+                student,
+                payment: course.price,
+                excess,
+            });
+        }
+
+        // Helper function to drop a student from a course's enrollment bookkeeping
+        fn remove_enrollment(
+            &mut self,
+            mut course: Course,
+            student: AccountId,
+            status: EnrollmentStatus,
+        ) {
+            let course_id = course.id;
+
+            self.unindex_enrollment(course_id, student);
+            self.set_enrollment_status(course_id, student, status);
+
+            course.enrolled_count = course.enrolled_count.saturating_sub(1);
+            self.courses.insert(course_id, &course);
+        }
+
+        // Helper function to hand free seats to students from the front of the waitlist
+        fn promote_waitlist(&mut self, course_id: u32) {
+            let current_time = self.env().block_timestamp();
+            let steps = self.expire_seat_offers(course_id);
+            let tail = self.waitlist_tail.get(course_id).unwrap_or(0);
+            let mut head = self.waitlist_head.get(course_id).unwrap_or(0);
+
+            // Positions left empty by `leave_waitlist` count as steps too, so one call
+            // stays bounded however many students left the queue
+            let stop = head
+                .saturating_add(MAX_WAITLIST_STEPS.saturating_sub(steps))
+                .min(tail);
+            while head < stop {
+                let course = match self.courses.get(course_id) {
+                    Some(course) => course,
+                    None => break,
+                };
+
+                // Seats are only handed out while the course is still open for enrollment
+                if !course.active
+                    || current_time >= course.start_time
+                    || self.taken_seats(&course) >= course.max_students
+                {
+                    break;
+                }
+
+                // Positions left empty by `leave_waitlist` are skipped
+                let position = head;
+                head = head.saturating_add(1);
+                let student = match self.waitlist_at.take((course_id, position)) {
+                    Some(student) => student,
+                    None => continue,
+                };
+                let entry = match self.waitlist_entries.take((course_id, student)) {
+                    Some(entry) => entry,
+                    None => continue,
+                };
+
+                // Escrow that no longer covers a raised price, or of a student who
+                // enrolled in the meantime, is handed back instead
+                let underfunded = entry.escrowed > 0 && entry.escrowed < course.price;
+                if underfunded || self.verify_enrollment(student, course_id) {
+                    self.credit_withdrawal(student, course.payment_token, entry.escrowed);
+                    self.env().emit_event(WaitlistLeft {
+                        course_id,
+                        student,
+                        refunded: entry.escrowed,
+                    });
+                    continue;
+                }
+
+                // Students who joined without escrow are offered the seat and pay on enrolling
+                if entry.escrowed < course.price {
+                    let deadline = current_time.saturating_add(SEAT_OFFER_WINDOW);
+                    self.seat_offers.insert((course_id, student), &deadline);
+                    let offered = self.offered_seats.get(course_id).unwrap_or(0);
+                    self.offered_seats
+                        .insert(course_id, &offered.saturating_add(1));
+
+                    let offer_position = self.seat_offer_tail.get(course_id).unwrap_or(0);
+                    self.seat_offer_at
+                        .insert((course_id, offer_position), &(student, deadline));
+                    self.seat_offer_tail
+                        .insert(course_id, &offer_position.saturating_add(1));
+
+                    self.env().emit_event(SeatOffered {
+                        course_id,
+                        student,
+                        deadline,
+                    });
+                    continue;
+                }
+
+                let excess = entry.escrowed.saturating_sub(course.price);
+                if excess > 0 {
+                    self.credit_withdrawal(student, course.payment_token, excess);
+                }
+                self.record_enrollment(course, student, excess);
+
+                self.env()
+                    .emit_event(WaitlistPromoted { course_id, student });
+            }
+
+            self.waitlist_head.insert(course_id, &head);
+        }
+
+        // Helper function to take back seats of offers that ran out, returning the steps used
+        // Every offer gets the same window, so they expire in the order they were made
+        fn expire_seat_offers(&mut self, course_id: u32) -> u32 {
+            let current_time = self.env().block_timestamp();
+            let tail = self.seat_offer_tail.get(course_id).unwrap_or(0);
+            let mut head = self.seat_offer_head.get(course_id).unwrap_or(0);
+            let mut steps: u32 = 0;
+
+            while head < tail && steps < MAX_WAITLIST_STEPS {
+                if let Some((student, deadline)) = self.seat_offer_at.get((course_id, head)) {
+                    // Offers already taken up or declined are skipped
+                    if self.seat_offers.get((course_id, student)) == Some(deadline) {
+                        if current_time < deadline {
+                            break;
+                        }
+                        self.take_seat_offer(course_id, student);
+                        self.env().emit_event(WaitlistLeft {
+                            course_id,
+                            student,
+                            refunded: 0,
+                        });
+                    }
+                    self.seat_offer_at.remove((course_id, head));
+                }
+                head = head.saturating_add(1);
+                steps = steps.saturating_add(1);
+            }
+
+            self.seat_offer_head.insert(course_id, &head);
+            steps
+        }
+
+        // Helper function to count enrolled students and seats held by open offers
+        fn taken_seats(&self, course: &Course) -> u32 {
+            course
+                .enrolled_count
+                .saturating_add(self.offered_seats.get(course.id).unwrap_or(0))
+        }
+
+        // Helper function to drop a student's seat offer, freeing the seat it held
+        fn take_seat_offer(&mut self, course_id: u32, student: AccountId) -> bool {
+            if self.seat_offers.take((course_id, student)).is_none() {
+                return false;
+            }
+            let offered = self.offered_seats.get(course_id).unwrap_or(0);
+            self.offered_seats
+                .insert(course_id, &offered.saturating_sub(1));
+            true
+        }
+
+        // Helper function to move an enrollment record to a new status
+        fn set_enrollment_status(
+            &mut self,
+            course_id: u32,
+            student: AccountId,
+            status: EnrollmentStatus,
+        ) {
+            if let Some(mut record) = self.enrollments.get((course_id, student)) {
+                record.status = status;
+                self.enrollments.insert((course_id, student), &record);
+            }
+        }
+
+        // Helper function to check whether a student was certified, even if since revoked
+        fn is_completed(&self, course_id: u32, student: AccountId) -> bool {
+            self.course_completions
+                .get((course_id, student))
+                .unwrap_or(false)
+        }
+
+        // Helper function to check that an enrollment is neither completed nor failed
+        fn is_enrollment_active(&self, course_id: u32, student: AccountId) -> bool {
+            self.enrollments
+                .get((course_id, student))
+                .is_some_and(|record| record.status == EnrollmentStatus::Active)
+        }
+
+        // Helper function to append an enrollment to both indexes
+        fn index_enrollment(&mut self, course_id: u32, student: AccountId) {
+            let course_index = self.course_student_count.get(course_id).unwrap_or(0);
+            self.course_student_at
+                .insert((course_id, course_index), &student);
+            self.course_student_count
+                .insert(course_id, &course_index.saturating_add(1));
+
+            let student_index = self.student_course_count.get(student).unwrap_or(0);
+            self.student_course_at
+                .insert((student, student_index), &course_id);
+            self.student_course_count
+                .insert(student, &student_index.saturating_add(1));
+
+            self.enrollment_index.insert(
+                (course_id, student),
+                &EnrollmentIndex {
+                    course_index,
+                    student_index,
+                },
+            );
+        }
+
+        // Helper function to drop an enrollment from both indexes, moving the
+        // last entry of each index into the freed position
+        fn unindex_enrollment(&mut self, course_id: u32, student: AccountId) {
+            let record = match self.enrollment_index.take((course_id, student)) {
+                Some(record) => record,
+                None => return,
+            };
+
+            let last = self
+                .course_student_count
+                .get(course_id)
+                .unwrap_or(0)
+                .saturating_sub(1);
+            if record.course_index != last {
+                if let Some(moved) = self.course_student_at.get((course_id, last)) {
+                    self.course_student_at
+                        .insert((course_id, record.course_index), &moved);
+                    if let Some(mut moved_record) = self.enrollment_index.get((course_id, moved)) {
+                        moved_record.course_index = record.course_index;
+                        self.enrollment_index
+                            .insert((course_id, moved), &moved_record);
+                    }
+                }
+            }
+            self.course_student_at.remove((course_id, last));
+            self.course_student_count.insert(course_id, &last);
+
+            let last = self
+                .student_course_count
+                .get(student)
+                .unwrap_or(0)
+                .saturating_sub(1);
+            if record.student_index != last {
+                if let Some(moved) = self.student_course_at.get((student, last)) {
+                    self.student_course_at
+                        .insert((student, record.student_index), &moved);
+                    if let Some(mut moved_record) = self.enrollment_index.get((moved, student)) {
+                        moved_record.student_index = record.student_index;
+                        self.enrollment_index
+                            .insert((moved, student), &moved_record);
+                    }
+                }
+            }
+            self.student_course_at.remove((student, last));
+            self.student_course_count.insert(student, &last);
+        }
+
+        // Helper function to bring one course from `from_version` to the next layout
+        fn migrate_course(&mut self, from_version: u16, course_id: u32) {
+            match from_version {
+                1 => self.migrate_enrollment_lists(course_id),
+                2 => self.migrate_enrollment_records(course_id),
+                // Courses gained `prerequisites` in version 4, `completion_rule`
+                // in version 5 and `CompletionRule::min_attendance` in version 6
+                3..=5 => {
+                    if let Some(course) = self.legacy_course(course_id, from_version) {
+                        self.courses.insert(course_id, &course);
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        // Helper function to move a course's version 1 enrollment lists into the indexes
+        fn migrate_enrollment_lists(&mut self, course_id: u32) {
+            let students = self.course_students.take(course_id).unwrap_or_default();
+            for student in students {
+                if !self.enrollment_index.contains((course_id, student)) {
+                    self.index_enrollment(course_id, student);
+                }
+
+                let mut student_courses = self.student_enrollments.get(student).unwrap_or_default();
+                student_courses.retain(|id| *id != course_id);
+                if student_courses.is_empty() {
+                    self.student_enrollments.remove(student);
+                } else {
+                    self.student_enrollments.insert(student, &student_courses);
+                }
+            }
+        }
+
+        // Helper function to give every current enrollment of a course a full record,
+        // version 2 kept only the index positions under `enrollments`
+        fn migrate_enrollment_records(&mut self, course_id: u32) {
+            let course = match self.legacy_course(course_id, 2) {
+                Some(course) => course,
+                None => return,
+            };
+
+            for student in self.get_course_students(course_id) {
+                if !self.enrollment_index.contains((course_id, student)) {
+                    let legacy: Option<EnrollmentIndex> = ink::env::get_contract_storage(&(
+                        self.enrollments.key(),
+                        (course_id, student),
+                    ))
+                    .ok()
+                    .flatten();
+                    if let Some(index) = legacy {
+                        self.enrollment_index.insert((course_id, student), &index);
+                    }
+                }
+
+                // Enrollment time was not recorded before version 3
+                let amount_paid = self
+                    .escrowed_payments
+                    .get((course_id, student))
+                    .unwrap_or(course.price);
+                let status = if self.is_completed(course_id, student) {
+                    EnrollmentStatus::Completed
+                } else {
+                    EnrollmentStatus::Active
+                };
+                self.enrollments.insert(
+                    (course_id, student),
+                    &EnrollmentRecord {
+                        enrolled_at: 0,
+                        amount_paid,
+                        payment_asset: course.payment_token,
+                        status,
+                    },
+                );
+            }
+        }
+
+        // Helper function to read a course stored under the layout of `version`,
+        // which lacks the trailing fields added since; their encoded defaults
+        // are appended so the bytes decode as the current `Course`
+        fn legacy_course(&self, course_id: u32, version: u16) -> Option<Course> {
+            let RawEntry(mut bytes) =
+                ink::env::get_contract_storage::<_, RawEntry>(&(self.courses.key(), course_id))
+                    .ok()
+                    .flatten()?;
+
+            if version < 4 {
+                bytes.extend(scale::Encode::encode(&Vec::<u32>::new()));
+            }
+            if version < 5 {
+                bytes.extend(scale::Encode::encode(&Option::<CompletionRule>::None));
+            } else if version < 6 {
+                // A rule is the course's last field, a course without one
+                // leaves this byte unread
+                bytes.extend(scale::Encode::encode(&Option::<u32>::None));
+            }
+
+            <Course as scale::Decode>::decode(&mut &bytes[..]).ok()
+        }
+
+        // Helper function to take a student's payment back out of the course escrow
+        fn take_escrow(&mut self, course_id: u32, student: AccountId) -> Balance {
+            let amount = self.get_escrowed_payment(course_id, student);
+            if amount > 0 {
+                self.escrowed_payments.remove((course_id, student));
+                let course_escrow = self.course_escrow.get(course_id).unwrap_or(0);
+                self.course_escrow
+                    .insert(course_id, &course_escrow.saturating_sub(amount));
+            }
+            amount
+        }
+
+        // Helper function to add funds to an account's withdrawable balance
+        fn credit_withdrawal(
+            &mut self,
+            account: AccountId,
+            token: Option<AccountId>,
+            amount: Balance,
+        ) {
+            let pending = self.pending_balance(account, token);
+            self.set_pending_balance(account, token, pending.saturating_add(amount));
+        }
+
+        fn pending_balance(&self, account: AccountId, token: Option<AccountId>) -> Balance {
+            match token {
+                None => self.pending_withdrawals.get(account).unwrap_or(0),
+                Some(token) => self
+                    .pending_token_withdrawals
+                    .get((account, token))
+                    .unwrap_or(0),
+            }
+        }
+
+        fn set_pending_balance(
+            &mut self,
+            account: AccountId,
+            token: Option<AccountId>,
+            amount: Balance,
+        ) {
+            match token {
+                None => self.pending_withdrawals.insert(account, &amount),
+                Some(token) => self
+                    .pending_token_withdrawals
+                    .insert((account, token), &amount),
+            };
+        }
+
+        // Helper function to pay the caller out of their withdrawable balance
+        fn withdraw_funds(
+            &mut self,
+            token: Option<AccountId>,
+            amount: Balance,
+        ) -> Result<(), Error> {
+            self.ensure_not_paused()?;
+            let caller = self.env().caller();
+            let pending = self.pending_balance(caller, token);
+
+            if amount == 0 {
+                return Err(Error::InvalidInput);
+            }
+
+            if amount > pending {
+                return Err(Error::InsufficientBalance);
+            }
+            self.set_pending_balance(caller, token, pending.saturating_sub(amount));
+
+            self.pay_out(caller, token, amount)?;
+
+            self.env().emit_event(FundsWithdrawn {
+                account: caller,
+                token,
+                amount,
+            });
+
+            Ok(())
+        }
+
+        // Helper function to pay the owner out of the accrued platform fees
+        fn withdraw_fees(
+            &mut self,
+            token: Option<AccountId>,
+            amount: Balance,
+        ) -> Result<(), Error> {
+            self.ensure_owner()?;
+            let caller = self.env().caller();
+
+            let treasury = match token {
+                None => self.treasury,
+                Some(token) => self.get_token_treasury_balance(token),
+            };
+            if amount > treasury {
+                return Err(Error::InsufficientBalance);
+            }
+            match token {
+                None => self.treasury = treasury.saturating_sub(amount),
+                Some(token) => {
+                    self.token_treasury
+                        .insert(token, &treasury.saturating_sub(amount));
+                }
+            }
+
+            self.pay_out(caller, token, amount)?;
+
+            self.env().emit_event(TreasuryWithdrawn {
+                to: caller,
+                token,
+                amount,
+            });
+
+            Ok(())
+        }
+
+        // Helper function to send funds held by the contract in either currency
+        fn pay_out(
+            &self,
+            to: AccountId,
+            token: Option<AccountId>,
+            amount: Balance,
+        ) -> Result<(), Error> {
+            match token {
+                None => self
+                    .env()
+                    .transfer(to, amount)
+                    .map_err(|_| Error::PaymentFailed),
+                Some(token) => psp22::transfer(token, to, amount),
+            }
+        }
+
+        // Helper function to divide released revenue among a course's payees
+        // Rounding dust goes to the last payee so the shares always add up to `amount`
+        fn split_revenue(&self, course: &Course, amount: Balance) -> Vec<(AccountId, Balance)> {
+            if course.payees.is_empty() {
+                return Vec::from([(course.teacher, amount)]);
+            }
+
+            let mut shares = Vec::with_capacity(course.payees.len());
+            let mut remaining = amount;
+            for (index, payee) in course.payees.iter().enumerate() {
+                let share = if index + 1 == course.payees.len() {
+                    remaining
+                } else {
+                    amount
+                        .saturating_mul(Balance::from(payee.share_bps))
+                        .checked_div(BPS_DENOMINATOR)
+                        .unwrap_or(0)
+                };
+                remaining = remaining.saturating_sub(share);
+                shares.push((payee.account, share));
+            }
+            shares
+        }
+
+        // Helper function to compute when a course's refund window closes
+        fn refund_deadline(&self, course: &Course) -> Timestamp {
+            course.start_time.saturating_add(course.refund_window)
+        }
+
+        ////////////////////////////////////
+        ////////// HELPER/VIEW FUNCTIONS ////
+        //////////////////////////////////
+        #[ink(message)]
+        pub fn verify_enrollment(&self, student: AccountId, course_id: u32) -> bool {
+            self.enrollment_index.contains((course_id, student))
+        }
+
+        #[ink(message)]
+        pub fn get_lessons(&self, course_id: u32) -> Vec<Lesson> {
+            let count = self.lesson_count.get(course_id).unwrap_or(0);
+            (0..count)
+                .filter_map(|lesson_id| self.lessons.get((course_id, lesson_id)))
+                .collect()
+        }
+
+        #[ink(message)]
+        pub fn is_lesson_completed(
+            &self,
+            course_id: u32,
+            student: AccountId,
+            lesson_id: u32,
+        ) -> bool {
+            self.lesson_completions
+                .contains((course_id, student, lesson_id))
+        }
+
+        #[ink(message)]
+        pub fn get_lesson_progress(&self, course_id: u32, student: AccountId) -> u8 {
+            let total = self.lesson_count.get(course_id).unwrap_or(0);
+            let completed = self
+                .lessons_completed
+                .get((course_id, student))
+                .unwrap_or(0)
+                .min(total);
+            let percent = completed
+                .saturating_mul(100)
+                .checked_div(total)
+                .unwrap_or(0);
+            u8::try_from(percent).unwrap_or(100)
+        }
+
+        #[ink(message)]
+        pub fn get_assignments(&self, course_id: u32) -> Vec<Assignment> {
+            let count = self.assignment_count.get(course_id).unwrap_or(0);
+            (0..count)
+                .filter_map(|assignment_id| self.assignments.get((course_id, assignment_id)))
+                .collect()
+        }
+
+        #[ink(message)]
+        pub fn get_submission(
+            &self,
+            course_id: u32,
+            assignment_id: u32,
+            student: AccountId,
+        ) -> Option<Submission> {
+            self.submissions.get((course_id, assignment_id, student))
+        }
+
+        // Weighted average over all assignments and revealed quizzes in basis
+        // points, missing or ungraded work counts as zero
+        #[ink(message)]
+        pub fn get_aggregate_score(&self, course_id: u32, student: AccountId) -> u32 {
+            let mut weighted: Balance = 0;
+            let mut total_weight: Balance = 0;
+            for assignment_id in 0..self.assignment_count.get(course_id).unwrap_or(0) {
+                let assignment = match self.assignments.get((course_id, assignment_id)) {
+                    Some(assignment) => assignment,
+                    None => continue,
+                };
+                let score = self
+                    .submissions
+                    .get((course_id, assignment_id, student))
+                    .and_then(|submission| submission.score)
+                    .unwrap_or(0);
+
+                let weight = Balance::from(assignment.weight);
+                weighted = weighted.saturating_add(
+                    Balance::from(score)
+                        .saturating_mul(weight)
+                        .saturating_mul(BPS_DENOMINATOR)
+                        .checked_div(Balance::from(assignment.max_score))
+                        .unwrap_or(0),
+                );
+                total_weight = total_weight.saturating_add(weight);
+            }
+
+            // Quizzes count with their weight once their answer key is revealed
+            for quiz_id in 0..self.quiz_count.get(course_id).unwrap_or(0) {
+                let quiz = match self.quizzes.get((course_id, quiz_id)) {
+                    Some(quiz) if quiz.answer_key.is_some() => quiz,
+                    _ => continue,
+                };
+                let score = self
+                    .quiz_scores
+                    .get((course_id, quiz_id, student))
+                    .unwrap_or(0);
+
+                let weight = Balance::from(quiz.weight);
+                weighted = weighted.saturating_add(
+                    Balance::from(score)
+                        .saturating_mul(weight)
+                        .saturating_mul(BPS_DENOMINATOR)
+                        .checked_div(Balance::from(quiz.question_count))
+                        .unwrap_or(0),
+                );
+                total_weight = total_weight.saturating_add(weight);
+            }
+
+            let score = weighted.checked_div(total_weight).unwrap_or(0);
+            u32::try_from(score).unwrap_or(u32::MAX)
+        }
+
+        #[ink(message)]
+        pub fn get_quiz(&self, course_id: u32, quiz_id: u32) -> Option<Quiz> {
+            self.quizzes.get((course_id, quiz_id))
+        }
+
+        #[ink(message)]
+        pub fn get_quiz_score(
+            &self,
+            course_id: u32,
+            quiz_id: u32,
+            student: AccountId,
+        ) -> Option<u32> {
+            self.quiz_scores.get((course_id, quiz_id, student))
+        }
+
+        #[ink(message)]
+        pub fn get_quiz_points(&self, course_id: u32, student: AccountId) -> u32 {
+            self.quiz_points.get((course_id, student)).unwrap_or(0)
+        }
+
+        #[ink(message)]
+        pub fn get_sessions(&self, course_id: u32) -> Vec<Session> {
+            let count = self.session_count.get(course_id).unwrap_or(0);
+            (0..count)
+                .filter_map(|session_id| self.sessions.get((course_id, session_id)))
+                .collect()
+        }
+
+        #[ink(message)]
+        pub fn has_attended(&self, course_id: u32, session_id: u32, student: AccountId) -> bool {
+            self.attendance.contains((course_id, session_id, student))
+        }
+
+        #[ink(message)]
+        pub fn get_attendance_count(&self, course_id: u32, student: AccountId) -> u32 {
+            self.attendance_count.get((course_id, student)).unwrap_or(0)
+        }
+
+        #[ink(message)]
+        pub fn is_attendance_oracle(&self, account: AccountId) -> bool {
+            self.attendance_oracles.contains(account)
+        }
+
+        #[ink(message)]
+        pub fn get_waitlist(&self, course_id: u32) -> Vec<AccountId> {
+            let tail = self.waitlist_tail.get(course_id).unwrap_or(0);
+            (self.waitlist_head.get(course_id).unwrap_or(0)..tail)
+                .filter_map(|position| self.waitlist_at.get((course_id, position)))
+                .collect()
+        }
+
+        #[ink(message)]
+        pub fn get_seat_offer(&self, course_id: u32, student: AccountId) -> Option<Timestamp> {
+            // Offers lapse at their deadline or when the course is cancelled
+            let course = self.courses.get(course_id)?;
+            let current_time = self.env().block_timestamp();
+            self.seat_offers
+                .get((course_id, student))
+                .filter(|deadline| !course.cancelled && current_time < *deadline)
+        }
+
+        #[ink(message)]
+        pub fn get_waitlist_entry(
+            &self,
+            course_id: u32,
+            student: AccountId,
+        ) -> Option<WaitlistEntry> {
+            self.waitlist_entries.get((course_id, student))
+        }
+
+        #[ink(message)]
+        pub fn get_enrollment(
+            &self,
+            course_id: u32,
+            student: AccountId,
+        ) -> Option<EnrollmentRecord> {
+            self.enrollments.get((course_id, student))
+        }
+
+        #[ink(message)]
+        pub fn verify_completion(&self, student: AccountId, course_id: u32) -> bool {
+            // A revoked certificate no longer counts as a completion
+            self.is_completed(course_id, student)
+                && !self
+                    .completion_certificates
+                    .get((course_id, student))
+                    .is_some_and(|id| self.certificate_revocations.contains(id))
+        }
+
+        #[ink(message)]
+        pub fn get_course(&self, course_id: u32) -> Option<Course> {
+            self.courses.get(course_id)
+        }
+
+        #[ink(message)]
+        pub fn get_student_courses(&self, student: AccountId) -> Vec<u32> {
+            let count = self.student_course_count.get(student).unwrap_or(0);
+            (0..count)
+                .filter_map(|index| self.student_course_at.get((student, index)))
+                .collect()
+        }
+        // Add a method to get certificate attributes
+        #[ink(message)]
+        pub fn get_certificate_attribute(&self, id: psp34::Id, key: Vec<u8>) -> Option<Vec<u8>> {
+            self.attributes.get((id, key))
+        }
+        #[ink(message)]
+        pub fn get_student_certificate_vector(&self, student: AccountId) -> Vec<psp34::Id> {
+            self.student_certificates.get(student).unwrap_or_default()
+        }
+
+        #[ink(message)]
+        pub fn get_escrowed_payment(&self, course_id: u32, student: AccountId) -> Balance {
+            self.escrowed_payments
+                .get((course_id, student))
+                .unwrap_or(0)
+        }
+
+        #[ink(message)]
+        pub fn get_course_escrow(&self, course_id: u32) -> Balance {
+            self.course_escrow.get(course_id).unwrap_or(0)
+        }
+
+        #[ink(message)]
+        pub fn get_pending_withdrawal(&self, account: AccountId) -> Balance {
+            self.pending_withdrawals.get(account).unwrap_or(0)
+        }
+
+        #[ink(message)]
+        pub fn get_pending_token_withdrawal(
+            &self,
+            account: AccountId,
+            token: AccountId,
+        ) -> Balance {
+            self.pending_balance(account, Some(token))
+        }
+
+        #[ink(message)]
+        pub fn get_token_treasury_balance(&self, token: AccountId) -> Balance {
+            self.token_treasury.get(token).unwrap_or(0)
+        }
+
+        #[ink(message)]
+        pub fn get_owner(&self) -> AccountId {
+            self.owner
+        }
+
+        #[ink(message)]
+        pub fn get_pending_owner(&self) -> Option<AccountId> {
+            self.pending_owner
+        }
+
+        #[ink(message)]
+        pub fn is_paused(&self) -> bool {
+            self.paused
+        }
+
+        #[ink(message)]
+        pub fn check_eligibility(&self, course_id: u32, student: AccountId) -> Result<(), Error> {
+            let course = self.courses.get(course_id).ok_or(Error::CourseNotFound)?;
+            self.ensure_can_enroll(&course, student)
+        }
+
+        #[ink(message)]
+        pub fn get_storage_version(&self) -> u16 {
+            self.storage_version
+        }
+
+        #[ink(message)]
+        pub fn get_platform_fee(&self) -> u16 {
+            self.platform_fee_bps
+        }
+
+        #[ink(message)]
+        pub fn get_treasury_balance(&self) -> Balance {
+            self.treasury
+        }
+
+        #[ink(message)]
+        pub fn get_teacher_courses(&self, teacher: AccountId) -> Vec<u32> {
+            self.teacher_courses.get(teacher).unwrap_or_default()
+        }
+
+        #[ink(message)]
+        pub fn get_course_students(&self, course_id: u32) -> Vec<AccountId> {
+            let count = self.course_student_count.get(course_id).unwrap_or(0);
+            (0..count)
+                .filter_map(|index| self.course_student_at.get((course_id, index)))
+                .collect()
+        }
+
+        #[ink(message)]
+        pub fn get_course_roles(&self, course_id: u32) -> Vec<(AccountId, CourseRole)> {
+            self.course_role_members
+                .get(course_id)
+                .unwrap_or_default()
+                .into_iter()
+                .filter_map(|account| {
+                    self.course_roles
+                        .get((course_id, account))
+                        .map(|role| (account, role))
+                })
+                .collect()
+        }
+
+        #[ink(message)]
+        pub fn get_courses(&self) -> Vec<Course> {
+            let mut courses_vec = Vec::new();
+            for course_id in 0..self.course_counter {
+                if let Some(course) = self.courses.get(course_id) {
+                    courses_vec.push(course);
+                }
+            }
+            courses_vec
+        }
+        #[ink(message)]
+        pub fn get_courses_paged(
+            &self,
+            offset: u32,
+            limit: u32,
+            filter: CourseFilter,
+        ) -> CoursePage {
+            let current_time = self.env().block_timestamp();
+            // A page always holds at least one course so following the cursor terminates
+            let limit = limit.clamp(1, MAX_PAGE_SIZE);
+
+            // Bound both the page size and the ids inspected to keep gas predictable
+            let scan_end = offset
+                .saturating_add(MAX_PAGE_SCAN)
+                .min(self.course_counter);
+            let mut courses = Vec::new();
+            let mut course_id = offset;
+            while course_id < scan_end && (courses.len() as u32) < limit {
+                if let Some(course) = self.courses.get(course_id) {
+                    if filter.matches(&course, current_time) {
+                        courses.push(course);
+                    }
+                }
+                course_id = course_id.saturating_add(1);
+            }
+
+            let next_cursor = if course_id < self.course_counter {
+                Some(course_id)
+            } else {
+                None
+            };
+
+            CoursePage {
+                courses,
+                next_cursor,
+            }
+        }
+
+        #[ink(message)]
+        pub fn verify_certificate(&self, id: psp34::Id) -> Result<CertificateInfo, Error> {
+            if self.certificate_revocations.contains(id.clone()) {
+                return Err(Error::CertificateRevoked);
+            }
+            self.certificate_info.get(id).ok_or(Error::NFTMintingFailed)
+        }
+
+        #[ink(message)]
+        pub fn get_certificate_for(&self, course_id: u32, student: AccountId) -> Option<psp34::Id> {
+            self.completion_certificates.get((course_id, student))
+        }
+
+        #[ink(message)]
+        pub fn get_certificate_revocation(&self, id: psp34::Id) -> Option<RevocationRecord> {
+            self.certificate_revocations.get(id)
+        }
+    }
+    ////////////////////////////////////
+    ////////// PSP22 CALLS /////////////
+    //////////////////////////////////
+    mod psp22 {
+        use super::{AccountId, Balance, Error, PSP22Error};
+        use ink::env::call::{build_call, ExecutionInput, Selector};
+        use ink::env::DefaultEnvironment;
+        use ink::prelude::vec::Vec;
+
+        pub fn transfer(token: AccountId, to: AccountId, value: Balance) -> Result<(), Error> {
+            let result = build_call::<DefaultEnvironment>()
+                .call(token)
+                .exec_input(
+                    ExecutionInput::new(Selector::new(ink::selector_bytes!("PSP22::transfer")))
+                        .push_arg(to)
+                        .push_arg(value)
+                        .push_arg(Vec::<u8>::new()),
+                )
+                .returns::<Result<(), PSP22Error>>()
+                .try_invoke();
+
+            match result {
+                Ok(Ok(Ok(()))) => Ok(()),
+                _ => Err(Error::TransferError),
+            }
+        }
+
+        pub fn transfer_from(
+            token: AccountId,
+            from: AccountId,
+            to: AccountId,
+            value: Balance,
+        ) -> Result<(), Error> {
+            let result = build_call::<DefaultEnvironment>()
+                .call(token)
+                .exec_input(
+                    ExecutionInput::new(Selector::new(ink::selector_bytes!(
+                        "PSP22::transfer_from"
+                    )))
+                    .push_arg(from)
+                    .push_arg(to)
+                    .push_arg(value)
+                    .push_arg(Vec::<u8>::new()),
+                )
+                .returns::<Result<(), PSP22Error>>()
+                .try_invoke();
+
+            match result {
+                Ok(Ok(Ok(()))) => Ok(()),
+                _ => Err(Error::TransferError),
+            }
+        }
+    }
+
+    ////////////////////////////////////
+    ////////// PSP34 TRAIT IMPLS ///////
+    //////////////////////////////////
+    impl psp34::PSP34 for Eduverse {
+        #[ink(message)]
+        fn collection_id(&self) -> Id {
+            Id::U8(0)
+        }
+
+        #[ink(message)]
+        fn balance_of(&self, owner: AccountId) -> u32 {
+            self.data.balance_of(owner)
+        }
+
+        #[ink(message)]
+        fn owner_of(&self, id: Id) -> Option<AccountId> {
+            self.data.owner_of(&id)
+        }
+
+        #[ink(message)]
+        fn allowance(&self, owner: AccountId, operator: AccountId, id: Option<Id>) -> bool {
+            self.data.allowance(owner, operator, id.as_ref())
+        }
+
+        #[ink(message)]
+        fn approve(
+            &mut self,
+            operator: AccountId,
+            id: Option<Id>,
+            approved: bool,
+        ) -> Result<(), PSP34Error> {
+            let _events = self
+                .data
+                .approve(self.env().caller(), operator, id, approved)?;
+            // Handle events
+            Ok(())
+        }
+
+        #[ink(message)]
+        fn transfer(&mut self, _to: AccountId, _id: Id, _data: Vec<u8>) -> Result<(), PSP34Error> {
+            // Prevent transfers to maintain certificate integrity
+            return Err(PSP34Error::Custom(String::from(
+                "Certificates cannot be transferred",
+            )));
+        }
+
+        #[ink(message)]
+        fn total_supply(&self) -> u128 {
+            self.data.total_supply()
+        }
+    }
+
+    impl psp34::PSP34Metadata for Eduverse {
+        #[ink(message)]
+        fn get_attribute(&self, id: Id, key: Vec<u8>) -> Option<Vec<u8>> {
+            self.attributes.get((id, key))
+        }
+    }
+
+    ////////////////////////////////////
+    ////////// TEST HELPERS ////////////
+    //////////////////////////////////
+    /// Writes storage in the layouts of earlier versions, for the migration tests
+    #[cfg(test)]
+    impl Eduverse {
+        pub fn set_storage_version(&mut self, version: u16) {
+            self.storage_version = version;
+        }
+
+        /// Adds an enrollment to the version 1 lists
+        pub fn insert_v1_enrollment(&mut self, course_id: u32, student: AccountId) {
+            let mut student_courses = self.student_enrollments.get(student).unwrap_or_default();
+            student_courses.push(course_id);
+            self.student_enrollments.insert(student, &student_courses);
+
+            let mut course_students = self.course_students.get(course_id).unwrap_or_default();
+            course_students.push(student);
+            self.course_students.insert(course_id, &course_students);
+        }
+
+        /// Stores `course` without the trailing fields added after `version`
+        pub fn insert_legacy_course(&mut self, course: &Course, version: u16) {
+            let mut dropped = 0;
+            if version < 6 {
+                if let Some(rule) = &course.completion_rule {
+                    dropped = scale::Encode::encode(&rule.min_attendance).len();
+                }
+            }
+            if version < 5 {
+                dropped = scale::Encode::encode(&course.completion_rule).len();
+            }
+            if version < 4 {
+                dropped += scale::Encode::encode(&course.prerequisites).len();
+            }
+
+            let mut bytes = scale::Encode::encode(course);
+            bytes.truncate(bytes.len().saturating_sub(dropped));
+            ink::env::set_contract_storage(&(self.courses.key(), course.id), &RawEntry(bytes));
+        }
+    }
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use eduverse::Eduverse;
+    use ink::env::{test, DefaultEnvironment};
+    use ink::prelude::string::String;
+    use ink::prelude::vec::Vec;
+    use ink::primitives::AccountId;
+    use psp34::{Id, PSP34Error, PSP34Metadata, PSP34};
+
+    const COURSE_TITLE: &str = "Test Course";
+    const COURSE_DESC: &str = "This is a test course.";
+    const METADATA_HASH: &str = "hash123";
+    const MAX_STUDENTS: u32 = 10;
+    const PRICE: u128 = 100;
+    const REFUND_WINDOW: u64 = 500;
+
+    /// Helper to generate a string of a given length.
+    fn generate_string(len: usize) -> String {
+        "a".repeat(len)
+    }
+
+    /// Helper to create a course starting at 2000 and ending at 3000 as the current caller.
+    fn create_test_course(contract: &mut Eduverse, max_students: u32) -> u32 {
+        contract
+            .create_course(
+                String::from(COURSE_TITLE),
+                String::from(COURSE_DESC),
+                max_students,
+                2000,
+                3000,
+                PRICE,
+                String::from(METADATA_HASH),
+                REFUND_WINDOW,
+                None,
+                Vec::new(),
+            )
+            .expect("Course creation should succeed")
+    }
+
+    /// Helper to store a course and its prerequisite in the layout of `version` and check
+    /// that they read back unchanged after migrating, apart from the fields added since.
+    fn assert_migrates_courses_from(version: u16) {
+        let accounts = test::default_accounts::<DefaultEnvironment>();
+        test::set_caller::<DefaultEnvironment>(accounts.alice);
+        test::set_block_timestamp::<DefaultEnvironment>(1000);
+        let mut contract = Eduverse::new();
+        let first = create_test_course(&mut contract, MAX_STUDENTS);
+        let second = contract
+            .create_course(
+                String::from(COURSE_TITLE),
+                String::from(COURSE_DESC),
+                MAX_STUDENTS,
+                2000,
+                3000,
+                PRICE,
+                String::from(METADATA_HASH),
+                REFUND_WINDOW,
+                None,
+                vec![first],
+            )
+            .expect("Course creation should succeed");
+        assert!(contract
+            .set_completion_rule(
+                second,
+                Some(eduverse::CompletionRule {
+                    min_score: Some(5_000),
+                    all_lessons: true,
+                    min_attendance: None,
+                })
+            )
+            .is_ok());
+
+        let courses = [
+            contract.get_course(first).unwrap(),
+            contract.get_course(second).unwrap(),
+        ];
+        for course in courses.iter() {
+            contract.insert_legacy_course(course, version);
+        }
+        contract.set_storage_version(version);
+
+        assert!(contract.pause().is_ok());
+        while contract.get_storage_version() < eduverse::STORAGE_VERSION {
+            assert!(contract.migrate().is_ok());
+        }
+
+        for mut course in courses {
+            if version < 4 {
+                course.prerequisites = Vec::new();
+            }
+            if version < 5 {
+                course.completion_rule = None;
+            }
+            assert_eq!(contract.get_course(course.id), Some(course));
+        }
+    }
+
+    /// Test creating a course with an invalid title length (more than 100 characters).
+    #[ink::test]
+    fn test_create_course_invalid_title() {
+        let accounts = test::default_accounts::<DefaultEnvironment>();
+        test::set_caller::<DefaultEnvironment>(accounts.alice);
+        test::set_block_timestamp::<DefaultEnvironment>(1000);
+
+        let mut contract = Eduverse::new();
+
+        let invalid_title = generate_string(101); // 101 characters
+        let description = String::from(COURSE_DESC);
+        let start_time = 2000;
+        let end_time = 3000;
+        let price = PRICE;
+        let metadata_hash = String::from(METADATA_HASH);
+
+        let result = contract.create_course(
+            invalid_title,
+            description,
+            MAX_STUDENTS,
+            start_time,
+            end_time,
+            price,
+            metadata_hash,
+            REFUND_WINDOW,
+            None,
+            Vec::new(),
+        );
+        assert_eq!(result, Err(eduverse::Error::InvalidInput));
+    }
+
+    /// Test creating a course with an invalid description length (more than 1000 characters).
+    #[ink::test]
+    fn test_create_course_invalid_description() {
+        let accounts = test::default_accounts::<DefaultEnvironment>();
+        test::set_caller::<DefaultEnvironment>(accounts.alice);
+        test::set_block_timestamp::<DefaultEnvironment>(1000);
+
+        let mut contract = Eduverse::new();
+
+        let title = String::from(COURSE_TITLE);
+        let invalid_description = generate_string(1001); // 1001 characters
+        let start_time = 2000;
+        let end_time = 3000;
+        let price = PRICE;
+        let metadata_hash = String::from(METADATA_HASH);
+
+        let result = contract.create_course(
+            title,
+            invalid_description,
+            MAX_STUDENTS,
+            start_time,
+            end_time,
+            price,
+            metadata_hash,
+            REFUND_WINDOW,
+            None,
+            Vec::new(),
+        );
+        assert_eq!(result, Err(eduverse::Error::InvalidInput));
+    }
+
+    /// Test creating a course with invalid timing (start_time in the past).
+    #[ink::test]
+    fn test_create_course_invalid_time_start() {
+        let accounts = test::default_accounts::<DefaultEnvironment>();
+        test::set_caller::<DefaultEnvironment>(accounts.alice);
+        // current block time is 1000
+        test::set_block_timestamp::<DefaultEnvironment>(1000);
+
+        let mut contract = Eduverse::new();
+
+        let title = String::from(COURSE_TITLE);
+        let description = String::from(COURSE_DESC);
+        let start_time = 500; // Invalid: before current time
+        let end_time = 3000;
+        let price = PRICE;
+        let metadata_hash = String::from(METADATA_HASH);
+
+        let result = contract.create_course(
+            title,
+            description,
+            MAX_STUDENTS,
+            start_time,
+            end_time,
+            price,
+            metadata_hash,
+            REFUND_WINDOW,
+            None,
+            Vec::new(),
+        );
+        assert_eq!(result, Err(eduverse::Error::InvalidTime));
+    }
+
+    /// Test creating a course with invalid timing (end_time <= start_time).
+    #[ink::test]
+    fn test_create_course_invalid_time_end() {
+        let accounts = test::default_accounts::<DefaultEnvironment>();
+        test::set_caller::<DefaultEnvironment>(accounts.alice);
+        // current block time is 1000
+        test::set_block_timestamp::<DefaultEnvironment>(1000);
+
+        let mut contract = Eduverse::new();
+
+        let title = String::from(COURSE_TITLE);
+        let description = String::from(COURSE_DESC);
+        let start_time = 2000;
+        let end_time = 1500; // Invalid: end_time before start_time
+        let price = PRICE;
+        let metadata_hash = String::from(METADATA_HASH);
+
+        let result = contract.create_course(
+            title,
+            description,
+            MAX_STUDENTS,
+            start_time,
+            end_time,
+            price,
+            metadata_hash,
+            REFUND_WINDOW,
+            None,
+            Vec::new(),
+        );
+        assert_eq!(result, Err(eduverse::Error::InvalidTime));
+    }
+
+    /// Test enrolling a student with insufficient payment.
+    #[ink::test]
+    fn test_enroll_insufficient_payment() {
+        let accounts = test::default_accounts::<DefaultEnvironment>();
+        // Create course as Alice.
+        test::set_caller::<DefaultEnvironment>(accounts.alice);
+        test::set_block_timestamp::<DefaultEnvironment>(1000);
+        let mut contract = Eduverse::new();
+
+        let title = String::from("Insufficient Payment Course");
+        let description = String::from("Test insufficient payment.");
+        let start_time = 2000;
+        let end_time = 3000;
+        let price = PRICE;
+        let metadata_hash = String::from("insuff_hash");
+
+        let course_id = contract
+            .create_course(
+                title,
+                description,
+                MAX_STUDENTS,
+                start_time,
+                end_time,
+                price,
+                metadata_hash,
+                REFUND_WINDOW,
+                None,
+                Vec::new(),
+            )
+            .expect("Course creation should succeed");
+
+        // Bob attempts to enroll with insufficient funds.
+        test::set_caller::<DefaultEnvironment>(accounts.bob);
+        // Transferred value less than price.
+        test::set_value_transferred::<DefaultEnvironment>(PRICE - 1);
+        test::set_block_timestamp::<DefaultEnvironment>(1500);
+
+        let enroll_result = contract.enroll(course_id);
+        assert_eq!(enroll_result, Err(eduverse::Error::InsufficientPayment));
+    }
+
+    /// Test enrolling a student when the course is not active.
+    #[ink::test]
+    fn test_enroll_course_not_active() {
+        let accounts = test::default_accounts::<DefaultEnvironment>();
+        // Create a course as Alice.
+        test::set_caller::<DefaultEnvironment>(accounts.alice);
+        test::set_block_timestamp::<DefaultEnvironment>(1000);
+        let mut contract = Eduverse::new();
+
+        let title = String::from("Inactive Course");
+        let description = String::from("Test course inactive.");
+        let start_time = 2000;
+        let end_time = 3000;
+        let price = PRICE;
+        let metadata_hash = String::from("inactive_hash");
+
+        let course_id = contract
+            .create_course(
+                title,
+                description,
+                MAX_STUDENTS,
+                start_time,
+                end_time,
+                price,
+                metadata_hash,
+                REFUND_WINDOW,
+                None,
+                Vec::new(),
+            )
+            .expect("Course creation should succeed");
+
+        // Update the course to set active = false.
+        let update_result =
+            contract.update_course(course_id, None, None, None, None, Some(false), None, None);
+        assert!(update_result.is_ok());
+
+        // Bob attempts to enroll.
+        test::set_caller::<DefaultEnvironment>(accounts.bob);
+        test::set_value_transferred::<DefaultEnvironment>(price);
+        test::set_block_timestamp::<DefaultEnvironment>(1500);
+
+        let enroll_result = contract.enroll(course_id);
+        assert_eq!(enroll_result, Err(eduverse::Error::CourseNotActive));
+    }
+
+    /// Test enrolling a student when the course is already in progress.
+    #[ink::test]
+    fn test_enroll_course_in_progress() {
+        let accounts = test::default_accounts::<DefaultEnvironment>();
+        // Create a course as Alice.
+        test::set_caller::<DefaultEnvironment>(accounts.alice);
+        test::set_block_timestamp::<DefaultEnvironment>(1000);
+        let mut contract = Eduverse::new();
+
+        let title = String::from("In Progress Course");
+        let description = String::from("Test course in progress.");
+        let start_time = 2000;
+        let end_time = 3000;
+        let price = PRICE;
+        let metadata_hash = String::from("inprog_hash");
+
+        let course_id = contract
+            .create_course(
+                title,
+                description,
+                MAX_STUDENTS,
+                start_time,
+                end_time,
+                price,
+                metadata_hash,
+                REFUND_WINDOW,
+                None,
+                Vec::new(),
+            )
+            .expect("Course creation should succeed");
+
+        // Bob attempts to enroll after the course has started.
+        test::set_caller::<DefaultEnvironment>(accounts.bob);
+        test::set_value_transferred::<DefaultEnvironment>(price);
+        test::set_block_timestamp::<DefaultEnvironment>(2100); // after start_time
+
+        let enroll_result = contract.enroll(course_id);
+        assert_eq!(enroll_result, Err(eduverse::Error::CourseInProgress));
+    }
+
+    /// Test enrolling a student who is already enrolled.
+    #[ink::test]
+    fn test_already_enrolled() {
+        let accounts = test::default_accounts::<DefaultEnvironment>();
+        // Create a course as Alice.
+        test::set_caller::<DefaultEnvironment>(accounts.alice);
+        test::set_block_timestamp::<DefaultEnvironment>(1000);
+        let mut contract = Eduverse::new();
+
+        let title = String::from("Double Enrollment Course");
+        let description = String::from("Test already enrolled.");
+        let start_time = 2000;
+        let end_time = 3000;
+        let price = PRICE;
+        let metadata_hash = String::from("double_hash");
+
+        let course_id = contract
+            .create_course(
+                title,
+                description,
+                2, // allow 2 students
+                start_time,
+                end_time,
+                price,
+                metadata_hash,
+                REFUND_WINDOW,
+                None,
+                Vec::new(),
+            )
+            .expect("Course creation should succeed");
+
+        // Bob enrolls successfully.
+        test::set_caller::<DefaultEnvironment>(accounts.bob);
+        test::set_value_transferred::<DefaultEnvironment>(price);
+        test::set_block_timestamp::<DefaultEnvironment>(1500);
+        let enroll_result = contract.enroll(course_id);
+        assert!(enroll_result.is_ok());
+
+        // Bob tries to enroll again.
+        test::set_value_transferred::<DefaultEnvironment>(price);
+        let second_enroll = contract.enroll(course_id);
+        assert_eq!(second_enroll, Err(eduverse::Error::AlreadyEnrolled));
+    }
+
+    /// Test enrolling a student when the course is full.
+    #[ink::test]
+    fn test_course_full() {
+        let accounts = test::default_accounts::<DefaultEnvironment>();
+        // Create a course as Alice with max_students = 1.
+        test::set_caller::<DefaultEnvironment>(accounts.alice);
+        test::set_block_timestamp::<DefaultEnvironment>(1000);
+        let mut contract = Eduverse::new();
+
+        let title = String::from("Full Course");
+        let description = String::from("Test course full.");
+        let start_time = 2000;
+        let end_time = 3000;
+        let price = PRICE;
+        let metadata_hash = String::from("full_hash");
+
+        let course_id = contract
+            .create_course(
+                title,
+                description,
+                1, // only one student allowed
+                start_time,
+                end_time,
+                price,
+                metadata_hash,
+                REFUND_WINDOW,
+                None,
+                Vec::new(),
+            )
+            .expect("Course creation should succeed");
+
+        // Bob enrolls successfully.
+        test::set_caller::<DefaultEnvironment>(accounts.bob);
+        test::set_value_transferred::<DefaultEnvironment>(price);
+        test::set_block_timestamp::<DefaultEnvironment>(1500);
+        let enroll_result = contract.enroll(course_id);
+        assert!(enroll_result.is_ok());
+
+        // Charlie attempts to enroll and should fail.
+        test::set_caller::<DefaultEnvironment>(accounts.charlie);
+        test::set_value_transferred::<DefaultEnvironment>(price);
+        let enroll_result_charlie = contract.enroll(course_id);
+        assert_eq!(enroll_result_charlie, Err(eduverse::Error::CourseIsFull));
+    }
+
+    /// Test updating a course after it has already started.
+    #[ink::test]
+    fn test_update_course_after_start() {
+        let accounts = test::default_accounts::<DefaultEnvironment>();
+        // Create a course as Alice.
+        test::set_caller::<DefaultEnvironment>(accounts.alice);
+        test::set_block_timestamp::<DefaultEnvironment>(1000);
+        let mut contract = Eduverse::new();
+
+        let title = String::from("Update Timing Course");
+        let description = String::from("Test update after start.");
+        let start_time = 2000;
+        let end_time = 3000;
+        let price = PRICE;
+        let metadata_hash = String::from("update_hash");
+
+        let course_id = contract
+            .create_course(
+                title,
+                description,
+                MAX_STUDENTS,
+                start_time,
+                end_time,
+                price,
+                metadata_hash,
+                REFUND_WINDOW,
+                None,
+                Vec::new(),
+            )
+            .expect("Course creation should succeed");
+
+        // Advance time past the start time.
+        test::set_block_timestamp::<DefaultEnvironment>(2100);
+
+        // Attempt to update the course.
+        let update_result = contract.update_course(
+            course_id,
+            Some(String::from("New Title")),
+            None,
+            None,
+            None,
+            None,
+            None,
+            None,
+        );
+        assert_eq!(update_result, Err(eduverse::Error::CourseInProgress));
+    }
+
+    /// Test completing a course by someone who is not the teacher.
+    #[ink::test]
+    fn test_complete_course_non_teacher() {
+        let accounts = test::default_accounts::<DefaultEnvironment>();
+        // Create a course as Alice.
+        test::set_caller::<DefaultEnvironment>(accounts.alice);
+        test::set_block_timestamp::<DefaultEnvironment>(1000);
+        let mut contract = Eduverse::new();
+
+        let title = String::from("Non-Teacher Completion Course");
+        let description = String::from("Test complete course by non-teacher.");
+        let start_time = 2000;
+        let end_time = 3000;
+        let price = PRICE;
+        let metadata_hash = String::from("nonteacher_hash");
+
+        let course_id = contract
+            .create_course(
+                title,
+                description,
+                MAX_STUDENTS,
+                start_time,
+                end_time,
+                price,
+                metadata_hash,
+                REFUND_WINDOW,
+                None,
+                Vec::new(),
+            )
+            .expect("Course creation should succeed");
+
+        // Bob enrolls.
+        test::set_caller::<DefaultEnvironment>(accounts.bob);
+        test::set_value_transferred::<DefaultEnvironment>(price);
+        test::set_block_timestamp::<DefaultEnvironment>(1500);
+        let enroll_result = contract.enroll(course_id);
+        assert!(enroll_result.is_ok());
+
+        // Advance time past the end time.
+        test::set_block_timestamp::<DefaultEnvironment>(3500);
+
+        // Bob (not the teacher) attempts to mark the course complete.
+        test::set_caller::<DefaultEnvironment>(accounts.bob);
+        let complete_result = contract.complete_course(course_id, accounts.bob);
+        assert_eq!(complete_result, Err(eduverse::Error::Unauthorized));
+    }
+
+    /// Test completing a course before the course has ended.
+    #[ink::test]
+    fn test_complete_course_before_end() {
+        let accounts = test::default_accounts::<DefaultEnvironment>();
+        // Create a course as Alice.
+        test::set_caller::<DefaultEnvironment>(accounts.alice);
+        test::set_block_timestamp::<DefaultEnvironment>(1000);
+        let mut contract = Eduverse::new();
+
+        let title = String::from("Early Completion Course");
+        let description = String::from("Test complete course too early.");
+        let start_time = 2000;
+        let end_time = 3000;
+        let price = PRICE;
+        let metadata_hash = String::from("early_hash");
+
+        let course_id = contract
+            .create_course(
+                title,
+                description,
+                MAX_STUDENTS,
+                start_time,
+                end_time,
+                price,
+                metadata_hash,
+                REFUND_WINDOW,
+                None,
+                Vec::new(),
+            )
+            .expect("Course creation should succeed");
+
+        // Bob enrolls.
+        test::set_caller::<DefaultEnvironment>(accounts.bob);
+        test::set_value_transferred::<DefaultEnvironment>(price);
+        test::set_block_timestamp::<DefaultEnvironment>(1500);
+        let enroll_result = contract.enroll(course_id);
+        assert!(enroll_result.is_ok());
+
+        // Advance time to between start and end.
+        test::set_block_timestamp::<DefaultEnvironment>(2500);
+
+        // Teacher attempts to complete the course too early.
+        test::set_caller::<DefaultEnvironment>(accounts.alice);
+        let complete_result = contract.complete_course(course_id, accounts.bob);
+        assert_eq!(complete_result, Err(eduverse::Error::CourseInProgress));
+    }
+
+    /// Test that NFT transfers are rejected.
+    #[ink::test]
+    fn test_transfer_rejection() {
+        let accounts = test::default_accounts::<DefaultEnvironment>();
+        // Create a course and complete it to mint an NFT.
+        test::set_caller::<DefaultEnvironment>(accounts.alice);
+        test::set_block_timestamp::<DefaultEnvironment>(1000);
+        let mut contract = Eduverse::new();
+
+        let title = String::from("Transfer Test Course");
+        let description = String::from("Test NFT transfer rejection.");
+        let start_time = 2000;
+        let end_time = 3000;
+        let price = PRICE;
+        let metadata_hash = String::from("transfer_hash");
+
+        let course_id = contract
+            .create_course(
+                title,
+                description,
+                MAX_STUDENTS,
+                start_time,
+                end_time,
+                price,
+                metadata_hash,
+                REFUND_WINDOW,
+                None,
+                Vec::new(),
+            )
+            .expect("Course creation should succeed");
+
+        // Bob enrolls.
+        test::set_caller::<DefaultEnvironment>(accounts.bob);
+        test::set_value_transferred::<DefaultEnvironment>(price);
+        test::set_block_timestamp::<DefaultEnvironment>(1500);
+        let enroll_result = contract.enroll(course_id);
+        assert!(enroll_result.is_ok());
+
+        // Advance time past course end.
+        test::set_block_timestamp::<DefaultEnvironment>(3500);
+
+        // Teacher completes the course, minting the NFT.
+        test::set_caller::<DefaultEnvironment>(accounts.alice);
+        let complete_result = contract.complete_course(course_id, accounts.bob);
+        assert!(complete_result.is_ok());
+
+        // Try transferring the NFT (should be rejected).
+        let bob_certs = contract.get_student_certificate_vector(accounts.bob);
+        assert!(!bob_certs.is_empty());
+        let certificate_id = bob_certs[0].clone();
+        // Attempt to transfer from Bob to Charlie.
+        test::set_caller::<DefaultEnvironment>(accounts.bob);
+        let transfer_result = contract.transfer(accounts.charlie, certificate_id, Vec::new());
+        match transfer_result {
+            Err(PSP34Error::Custom(ref msg)) => {
+                assert_eq!(msg, "Certificates cannot be transferred");
+            }
+            _ => panic!("Transfer should be rejected"),
+        }
+    }
+
+    /// Test creating a course whose refund window extends past the end time.
+    #[ink::test]
+    fn test_create_course_refund_window_too_long() {
+        let accounts = test::default_accounts::<DefaultEnvironment>();
+        test::set_caller::<DefaultEnvironment>(accounts.alice);
+        test::set_block_timestamp::<DefaultEnvironment>(1000);
+
+        let mut contract = Eduverse::new();
+
+        let result = contract.create_course(
+            String::from(COURSE_TITLE),
+            String::from(COURSE_DESC),
+            MAX_STUDENTS,
+            2000,
+            3000,
+            PRICE,
+            String::from(METADATA_HASH),
+            1001, // refund deadline would be after end_time
+            None,
+            Vec::new(),
+        );
+        assert_eq!(result, Err(eduverse::Error::InvalidTime));
+    }
+
+    /// Test requesting a refund within the refund window.
+    #[ink::test]
+    fn test_request_refund_within_window() {
+        let accounts = test::default_accounts::<DefaultEnvironment>();
+        test::set_caller::<DefaultEnvironment>(accounts.alice);
+        test::set_block_timestamp::<DefaultEnvironment>(1000);
+        let mut contract = Eduverse::new();
+        let course_id = create_test_course(&mut contract, MAX_STUDENTS);
+
+        // Bob enrolls and the payment is held in escrow.
+        test::set_caller::<DefaultEnvironment>(accounts.bob);
+        test::set_value_transferred::<DefaultEnvironment>(PRICE);
+        test::set_block_timestamp::<DefaultEnvironment>(1500);
+        assert!(contract.enroll(course_id).is_ok());
+        assert_eq!(
+            contract.get_escrowed_payment(course_id, accounts.bob),
+            PRICE
+        );
+        assert_eq!(contract.get_course_escrow(course_id), PRICE);
+
+        // Bob asks for a refund after the start but inside the window.
+        test::set_value_transferred::<DefaultEnvironment>(0);
+        test::set_block_timestamp::<DefaultEnvironment>(2200);
+        assert!(contract.request_refund(course_id).is_ok());
+
+        assert!(!contract.verify_enrollment(accounts.bob, course_id));
+        assert!(contract.get_student_courses(accounts.bob).is_empty());
+        assert_eq!(contract.get_course(course_id).unwrap().enrolled_count, 0);
+        assert_eq!(contract.get_escrowed_payment(course_id, accounts.bob), 0);
+        assert_eq!(contract.get_course_escrow(course_id), 0);
+        assert_eq!(
+            contract
+                .get_enrollment(course_id, accounts.bob)
+                .unwrap()
+                .status,
+            eduverse::EnrollmentStatus::Refunded
+        );
+
+        // Bob is no longer enrolled, so a second refund is rejected.
+        assert_eq!(
+            contract.request_refund(course_id),
+            Err(eduverse::Error::NotEnrolled)
+        );
+    }
+
+    /// Test requesting a refund after the refund window has closed.
+    #[ink::test]
+    fn test_request_refund_after_window() {
+        let accounts = test::default_accounts::<DefaultEnvironment>();
+        test::set_caller::<DefaultEnvironment>(accounts.alice);
+        test::set_block_timestamp::<DefaultEnvironment>(1000);
+        let mut contract = Eduverse::new();
+        let course_id = create_test_course(&mut contract, MAX_STUDENTS);
+
+        test::set_caller::<DefaultEnvironment>(accounts.bob);
+        test::set_value_transferred::<DefaultEnvironment>(PRICE);
+        test::set_block_timestamp::<DefaultEnvironment>(1500);
+        assert!(contract.enroll(course_id).is_ok());
+
+        // Window closes at start_time + REFUND_WINDOW = 2500.
+        test::set_block_timestamp::<DefaultEnvironment>(2500);
+        assert_eq!(
+            contract.request_refund(course_id),
+            Err(eduverse::Error::RefundPeriodEnded)
+        );
+        assert!(contract.verify_enrollment(accounts.bob, course_id));
+    }
+
+    /// Test releasing escrowed payments to the teacher only after the refund window.
+    #[ink::test]
+    fn test_release_payments() {
+        let accounts = test::default_accounts::<DefaultEnvironment>();
+        test::set_caller::<DefaultEnvironment>(accounts.alice);
+        test::set_block_timestamp::<DefaultEnvironment>(1000);
+        let mut contract = Eduverse::new();
+        let course_id = create_test_course(&mut contract, MAX_STUDENTS);
+
+        test::set_caller::<DefaultEnvironment>(accounts.bob);
+        test::set_value_transferred::<DefaultEnvironment>(PRICE);
+        test::set_block_timestamp::<DefaultEnvironment>(1500);
+        assert!(contract.enroll(course_id).is_ok());
+        test::set_value_transferred::<DefaultEnvironment>(0);
+
+        // Release is blocked while refunds are still possible.
+        test::set_block_timestamp::<DefaultEnvironment>(2400);
+        assert_eq!(
+            contract.release_payments(course_id),
+            Err(eduverse::Error::RefundPeriodActive)
+        );
+
+        test::set_block_timestamp::<DefaultEnvironment>(2500);
+        assert!(contract.release_payments(course_id).is_ok());
+        assert_eq!(contract.get_course_escrow(course_id), 0);
+        assert_eq!(contract.get_pending_withdrawal(accounts.alice), PRICE);
+
+        // Nothing is left to release a second time.
+        assert_eq!(
+            contract.release_payments(course_id),
+            Err(eduverse::Error::NothingToRelease)
+        );
+    }
+
+    /// Test that only the owner can set the platform fee and that it is bounded.
+    #[ink::test]
+    fn test_set_platform_fee() {
+        let accounts = test::default_accounts::<DefaultEnvironment>();
+        test::set_caller::<DefaultEnvironment>(accounts.alice);
+        let mut contract = Eduverse::new();
+
+        assert_eq!(
+            contract.set_platform_fee(eduverse::MAX_PLATFORM_FEE_BPS + 1),
+            Err(eduverse::Error::InvalidFeePercentage)
+        );
+        assert!(contract.set_platform_fee(500).is_ok());
+        assert_eq!(contract.get_platform_fee(), 500);
+
+        // Bob is not the owner.
+        test::set_caller::<DefaultEnvironment>(accounts.bob);
+        assert_eq!(
+            contract.set_platform_fee(100),
+            Err(eduverse::Error::NotOwner)
+        );
+        assert_eq!(contract.get_platform_fee(), 500);
+    }
+
+    /// Test that released payments are split between the teacher and the treasury.
+    #[ink::test]
+    fn test_platform_fee_accrues_to_treasury() {
+        let accounts = test::default_accounts::<DefaultEnvironment>();
+        // Alice owns the contract and sets a 10% fee.
+        test::set_caller::<DefaultEnvironment>(accounts.alice);
+        test::set_block_timestamp::<DefaultEnvironment>(1000);
+        let mut contract = Eduverse::new();
+        assert!(contract.set_platform_fee(1_000).is_ok());
+
+        // Charlie teaches the course.
+        test::set_caller::<DefaultEnvironment>(accounts.charlie);
+        let course_id = create_test_course(&mut contract, MAX_STUDENTS);
+        assert_eq!(
+            contract.get_course(course_id).unwrap().platform_fee_bps,
+            1_000
+        );
+
+        test::set_caller::<DefaultEnvironment>(accounts.bob);
+        test::set_value_transferred::<DefaultEnvironment>(PRICE);
+        test::set_block_timestamp::<DefaultEnvironment>(1500);
+        assert!(contract.enroll(course_id).is_ok());
+        test::set_value_transferred::<DefaultEnvironment>(0);
+
+        test::set_block_timestamp::<DefaultEnvironment>(2500);
+        assert!(contract.release_payments(course_id).is_ok());
+        assert_eq!(contract.get_treasury_balance(), PRICE / 10);
+
+        // Only the owner may withdraw, and not more than has accrued.
+        assert_eq!(
+            contract.withdraw_treasury(PRICE / 10),
+            Err(eduverse::Error::NotOwner)
+        );
+        test::set_caller::<DefaultEnvironment>(accounts.alice);
+        assert_eq!(
+            contract.withdraw_treasury(PRICE),
+            Err(eduverse::Error::InsufficientBalance)
+        );
+        assert!(contract.withdraw_treasury(PRICE / 10).is_ok());
+        assert_eq!(contract.get_treasury_balance(), 0);
+    }
+
+    /// Test that teachers pull released funds and cannot withdraw more than credited.
+    #[ink::test]
+    fn test_withdraw() {
+        let accounts = test::default_accounts::<DefaultEnvironment>();
+        test::set_caller::<DefaultEnvironment>(accounts.alice);
+        test::set_block_timestamp::<DefaultEnvironment>(1000);
+        let mut contract = Eduverse::new();
+        let course_id = create_test_course(&mut contract, MAX_STUDENTS);
+
+        test::set_caller::<DefaultEnvironment>(accounts.bob);
+        test::set_value_transferred::<DefaultEnvironment>(PRICE);
+        test::set_block_timestamp::<DefaultEnvironment>(1500);
+        assert!(contract.enroll(course_id).is_ok());
+        test::set_value_transferred::<DefaultEnvironment>(0);
+
+        // Bob has nothing to withdraw.
+        assert_eq!(
+            contract.withdraw(1),
+            Err(eduverse::Error::InsufficientBalance)
+        );
+
+        test::set_block_timestamp::<DefaultEnvironment>(2500);
+        assert!(contract.release_payments(course_id).is_ok());
+
+        test::set_caller::<DefaultEnvironment>(accounts.alice);
+        assert_eq!(
+            contract.withdraw(PRICE + 1),
+            Err(eduverse::Error::InsufficientBalance)
+        );
+        assert!(contract.withdraw(PRICE / 2).is_ok());
+        assert_eq!(contract.get_pending_withdrawal(accounts.alice), PRICE / 2);
+        assert!(contract.withdraw(PRICE / 2).is_ok());
+        assert_eq!(contract.get_pending_withdrawal(accounts.alice), 0);
+    }
+
+    /// Test that overpaying for a course credits the surplus back to the student.
+    #[ink::test]
+    fn test_enroll_overpayment_credited() {
+        let accounts = test::default_accounts::<DefaultEnvironment>();
+        test::set_caller::<DefaultEnvironment>(accounts.alice);
+        test::set_block_timestamp::<DefaultEnvironment>(1000);
+        let mut contract = Eduverse::new();
+        let course_id = create_test_course(&mut contract, MAX_STUDENTS);
+
+        // Bob sends more than the course price.
+        test::set_caller::<DefaultEnvironment>(accounts.bob);
+        test::set_value_transferred::<DefaultEnvironment>(PRICE + 25);
+        test::set_block_timestamp::<DefaultEnvironment>(1500);
+        assert!(contract.enroll(course_id).is_ok());
+
+        // Only the price is escrowed, the rest is withdrawable by Bob.
+        assert_eq!(
+            contract.get_escrowed_payment(course_id, accounts.bob),
+            PRICE
+        );
+        assert_eq!(contract.get_pending_withdrawal(accounts.bob), 25);
+
+        test::set_value_transferred::<DefaultEnvironment>(0);
+        assert!(contract.withdraw(25).is_ok());
+        assert_eq!(contract.get_pending_withdrawal(accounts.bob), 0);
+    }
+
+    /// Test that every student of a cancelled course can claim a refund.
+    #[ink::test]
+    fn test_cancel_course_refunds_students() {
+        let accounts = test::default_accounts::<DefaultEnvironment>();
+        test::set_caller::<DefaultEnvironment>(accounts.alice);
+        test::set_block_timestamp::<DefaultEnvironment>(1000);
+        let mut contract = Eduverse::new();
+
+        // Charlie teaches the course, Bob and Django enroll.
+        test::set_caller::<DefaultEnvironment>(accounts.charlie);
+        let course_id = create_test_course(&mut contract, MAX_STUDENTS);
+        test::set_block_timestamp::<DefaultEnvironment>(1500);
+        test::set_value_transferred::<DefaultEnvironment>(PRICE);
+        for student in [accounts.bob, accounts.django] {
+            test::set_caller::<DefaultEnvironment>(student);
+            assert!(contract.enroll(course_id).is_ok());
+        }
+        test::set_value_transferred::<DefaultEnvironment>(0);
+
+        // Eve is neither the teacher nor the owner.
+        test::set_caller::<DefaultEnvironment>(accounts.eve);
+        assert_eq!(
+            contract.cancel_course(course_id, String::from("reason")),
+            Err(eduverse::Error::Unauthorized)
+        );
+
+        test::set_caller::<DefaultEnvironment>(accounts.charlie);
+        assert!(contract
+            .cancel_course(course_id, String::from("reason"))
+            .is_ok());
+
+        let course = contract.get_course(course_id).unwrap();
+        assert!(course.cancelled);
+        assert!(!course.active);
+        assert_eq!(contract.get_course_escrow(course_id), 2 * PRICE);
+
+        // The escrow is neither released nor turned into certificates.
+        test::set_block_timestamp::<DefaultEnvironment>(3000);
+        assert_eq!(
+            contract.release_payments(course_id),
+            Err(eduverse::Error::CourseCancelled)
+        );
+        assert_eq!(
+            contract.complete_course(course_id, accounts.bob),
+            Err(eduverse::Error::CourseCancelled)
+        );
+
+        // Each student claims a full refund, also after the refund window.
+        for student in [accounts.bob, accounts.django] {
+            test::set_caller::<DefaultEnvironment>(student);
+            assert!(contract.request_refund(course_id).is_ok());
+            assert!(!contract.verify_enrollment(student, course_id));
+            assert!(contract.get_student_courses(student).is_empty());
+            assert_eq!(
+                contract.get_enrollment(course_id, student).unwrap().status,
+                eduverse::EnrollmentStatus::Refunded
+            );
+        }
+        assert_eq!(contract.get_course(course_id).unwrap().enrolled_count, 0);
+        assert!(contract.get_course_students(course_id).is_empty());
+        assert_eq!(contract.get_course_escrow(course_id), 0);
+
+        // The course can neither be cancelled again nor reopened.
+        test::set_caller::<DefaultEnvironment>(accounts.charlie);
+        assert_eq!(
+            contract.cancel_course(course_id, String::from("reason")),
+            Err(eduverse::Error::CourseCancelled)
+        );
+        assert_eq!(
+            contract.update_course(course_id, None, None, None, None, Some(true), None, None),
+            Err(eduverse::Error::CourseCancelled)
+        );
+    }
+
+    /// Test that the contract owner can take down a course for moderation.
+    #[ink::test]
+    fn test_owner_cancel_course() {
+        let accounts = test::default_accounts::<DefaultEnvironment>();
+        test::set_caller::<DefaultEnvironment>(accounts.alice);
+        test::set_block_timestamp::<DefaultEnvironment>(1000);
+        let mut contract = Eduverse::new();
+
+        test::set_caller::<DefaultEnvironment>(accounts.charlie);
+        let course_id = create_test_course(&mut contract, MAX_STUDENTS);
+
+        test::set_caller::<DefaultEnvironment>(accounts.alice);
+        assert!(contract
+            .cancel_course(course_id, String::from("takedown"))
+            .is_ok());
+        assert!(contract.get_course(course_id).unwrap().cancelled);
+    }
+
+    /// Test that a student can leave a course before it starts and free the seat.
+    #[ink::test]
+    fn test_unenroll_before_start() {
+        let accounts = test::default_accounts::<DefaultEnvironment>();
+        test::set_caller::<DefaultEnvironment>(accounts.alice);
+        test::set_block_timestamp::<DefaultEnvironment>(1000);
+        let mut contract = Eduverse::new();
+        let course_id = create_test_course(&mut contract, 1);
+
+        test::set_caller::<DefaultEnvironment>(accounts.bob);
+        test::set_value_transferred::<DefaultEnvironment>(PRICE);
+        test::set_block_timestamp::<DefaultEnvironment>(1500);
+        assert!(contract.enroll(course_id).is_ok());
+
+        test::set_value_transferred::<DefaultEnvironment>(0);
+        assert!(contract.unenroll(course_id).is_ok());
+        assert!(!contract.verify_enrollment(accounts.bob, course_id));
+        assert!(contract.get_course_students(course_id).is_empty());
+        assert_eq!(contract.get_course(course_id).unwrap().enrolled_count, 0);
+        assert_eq!(contract.get_course_escrow(course_id), 0);
+
+        // The freed seat can be taken by Charlie.
+        test::set_caller::<DefaultEnvironment>(accounts.charlie);
+        test::set_value_transferred::<DefaultEnvironment>(PRICE);
+        assert!(contract.enroll(course_id).is_ok());
+    }
+
+    /// Test that a student cannot leave a course once it has started.
+    #[ink::test]
+    fn test_unenroll_after_start() {
+        let accounts = test::default_accounts::<DefaultEnvironment>();
+        test::set_caller::<DefaultEnvironment>(accounts.alice);
+        test::set_block_timestamp::<DefaultEnvironment>(1000);
+        let mut contract = Eduverse::new();
+        let course_id = create_test_course(&mut contract, MAX_STUDENTS);
+
+        test::set_caller::<DefaultEnvironment>(accounts.bob);
+        test::set_value_transferred::<DefaultEnvironment>(PRICE);
+        test::set_block_timestamp::<DefaultEnvironment>(1500);
+        assert!(contract.enroll(course_id).is_ok());
+
+        test::set_value_transferred::<DefaultEnvironment>(0);
+        test::set_block_timestamp::<DefaultEnvironment>(2000);
+        assert_eq!(
+            contract.unenroll(course_id),
+            Err(eduverse::Error::CourseInProgress)
+        );
+        assert!(contract.verify_enrollment(accounts.bob, course_id));
+    }
+
+    /// Test that a course price can only be set in a token contract other than Eduverse.
+    /// Paying in the token is covered by the `token_course_payment` end-to-end test.
+    #[ink::test]
+    fn test_create_course_payment_token() {
+        let accounts = test::default_accounts::<DefaultEnvironment>();
+        let token = AccountId::from([0x42; 32]);
+        let contract_account = test::callee::<DefaultEnvironment>();
+        test::set_caller::<DefaultEnvironment>(accounts.alice);
+        test::set_block_timestamp::<DefaultEnvironment>(1000);
+        let mut contract = Eduverse::new();
+
+        let create_token_course = |contract: &mut Eduverse, token: AccountId| {
+            contract.create_course(
+                String::from(COURSE_TITLE),
+                String::from(COURSE_DESC),
+                MAX_STUDENTS,
+                2000,
+                3000,
+                PRICE,
+                String::from(METADATA_HASH),
+                REFUND_WINDOW,
+                Some(token),
+                Vec::new(),
+            )
+        };
+
+        // Neither Eduverse itself nor an account without code is a token.
+        test::set_caller::<DefaultEnvironment>(accounts.charlie);
+        assert_eq!(
+            create_token_course(&mut contract, contract_account),
+            Err(eduverse::Error::InvalidPaymentToken)
+        );
+        assert_eq!(
+            create_token_course(&mut contract, token),
+            Err(eduverse::Error::InvalidPaymentToken)
+        );
+
+        test::set_contract::<DefaultEnvironment>(token);
+        let course_id =
+            create_token_course(&mut contract, token).expect("Course creation should succeed");
+        assert_eq!(
+            contract.get_course(course_id).unwrap().payment_token,
+            Some(token)
+        );
+
+        // Native payments are not accepted for token priced courses.
+        test::set_caller::<DefaultEnvironment>(accounts.bob);
+        test::set_block_timestamp::<DefaultEnvironment>(1500);
+        test::set_value_transferred::<DefaultEnvironment>(PRICE);
+        assert_eq!(
+            contract.enroll(course_id),
+            Err(eduverse::Error::InvalidPaymentToken)
+        );
+    }
+
+    /// Test that native priced courses reject the token enrollment path.
+    #[ink::test]
+    fn test_enroll_with_token_native_course() {
+        let accounts = test::default_accounts::<DefaultEnvironment>();
+        test::set_caller::<DefaultEnvironment>(accounts.alice);
+        test::set_block_timestamp::<DefaultEnvironment>(1000);
+        let mut contract = Eduverse::new();
+        let course_id = create_test_course(&mut contract, MAX_STUDENTS);
+
+        test::set_caller::<DefaultEnvironment>(accounts.bob);
+        test::set_block_timestamp::<DefaultEnvironment>(1500);
+        assert_eq!(
+            contract.enroll_with_token(course_id),
+            Err(eduverse::Error::InvalidPaymentToken)
+        );
     }
-}
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use eduverse::Eduverse;
-    use ink::env::{test, DefaultEnvironment};
-    use ink::prelude::string::String;
-    use ink::prelude::vec::Vec;
-    use psp34::{PSP34Error, PSP34};
 
-    const COURSE_TITLE: &str = "Test Course";
-    const COURSE_DESC: &str = "This is a test course.";
-    const METADATA_HASH: &str = "hash123";
-    const MAX_STUDENTS: u32 = 10;
-    const PRICE: u128 = 100;
+    /// Test reading collection and certificate metadata through PSP34Metadata.
+    #[ink::test]
+    fn test_psp34_metadata() {
+        let accounts = test::default_accounts::<DefaultEnvironment>();
+        test::set_caller::<DefaultEnvironment>(accounts.alice);
+        test::set_block_timestamp::<DefaultEnvironment>(1000);
+        let mut contract = Eduverse::new();
 
-    /// Helper to generate a string of a given length.
-    fn generate_string(len: usize) -> String {
-        "a".repeat(len)
+        let collection_id = contract.collection_id();
+        assert_eq!(
+            contract.get_attribute(collection_id.clone(), b"name".to_vec()),
+            Some(b"Eduverse Certificates".to_vec())
+        );
+        assert_eq!(
+            contract.get_attribute(collection_id.clone(), b"symbol".to_vec()),
+            Some(b"EDUC".to_vec())
+        );
+
+        // Only the owner may set the base URI.
+        test::set_caller::<DefaultEnvironment>(accounts.bob);
+        assert_eq!(
+            contract.set_base_uri(String::from("ipfs://certs/")),
+            Err(eduverse::Error::NotOwner)
+        );
+        test::set_caller::<DefaultEnvironment>(accounts.alice);
+        assert!(contract.set_base_uri(String::from("ipfs://certs/")).is_ok());
+        assert_eq!(
+            contract.get_attribute(collection_id, b"baseURI".to_vec()),
+            Some(b"ipfs://certs/".to_vec())
+        );
+
+        // Complete a course for Bob to mint a certificate.
+        let course_id = create_test_course(&mut contract, MAX_STUDENTS);
+        test::set_caller::<DefaultEnvironment>(accounts.bob);
+        test::set_value_transferred::<DefaultEnvironment>(PRICE);
+        test::set_block_timestamp::<DefaultEnvironment>(1500);
+        assert!(contract.enroll(course_id).is_ok());
+        test::set_value_transferred::<DefaultEnvironment>(0);
+        test::set_block_timestamp::<DefaultEnvironment>(3500);
+        test::set_caller::<DefaultEnvironment>(accounts.alice);
+
+        let events_before = test::recorded_events().count();
+        assert!(contract.complete_course(course_id, accounts.bob).is_ok());
+        // Transfer, three attribute events and the completion event.
+        assert_eq!(test::recorded_events().count(), events_before + 5);
+
+        let certificate_id: Id = contract.get_student_certificate_vector(accounts.bob)[0].clone();
+        assert_eq!(
+            contract.get_attribute(certificate_id.clone(), b"course_title".to_vec()),
+            Some(String::from(COURSE_TITLE).into_bytes())
+        );
+        assert_eq!(
+            contract.get_attribute(certificate_id, b"course_id".to_vec()),
+            Some(course_id.to_string().into_bytes())
+        );
     }
 
-    /// Test creating a course with an invalid title length (more than 100 characters).
+    /// Test revoking a certificate and verifying it afterwards.
     #[ink::test]
-    fn test_create_course_invalid_title() {
+    fn test_revoke_certificate() {
+        let accounts = test::default_accounts::<DefaultEnvironment>();
+        test::set_caller::<DefaultEnvironment>(accounts.alice);
+        test::set_block_timestamp::<DefaultEnvironment>(1000);
+        let mut contract = Eduverse::new();
+
+        // Charlie teaches the course and certifies Bob.
+        test::set_caller::<DefaultEnvironment>(accounts.charlie);
+        let course_id = create_test_course(&mut contract, MAX_STUDENTS);
+        test::set_caller::<DefaultEnvironment>(accounts.bob);
+        test::set_value_transferred::<DefaultEnvironment>(PRICE);
+        test::set_block_timestamp::<DefaultEnvironment>(1500);
+        assert!(contract.enroll(course_id).is_ok());
+        test::set_value_transferred::<DefaultEnvironment>(0);
+        test::set_block_timestamp::<DefaultEnvironment>(3500);
+        test::set_caller::<DefaultEnvironment>(accounts.charlie);
+        assert!(contract.complete_course(course_id, accounts.bob).is_ok());
+
+        let certificate_id = contract.get_student_certificate_vector(accounts.bob)[0].clone();
+        assert!(contract.verify_certificate(certificate_id.clone()).is_ok());
+        assert!(contract.verify_completion(accounts.bob, course_id));
+
+        // Students cannot revoke certificates.
+        test::set_caller::<DefaultEnvironment>(accounts.bob);
+        assert_eq!(
+            contract.revoke_certificate(certificate_id.clone(), String::from("plagiarism")),
+            Err(eduverse::Error::Unauthorized)
+        );
+
+        test::set_caller::<DefaultEnvironment>(accounts.charlie);
+        assert!(contract
+            .revoke_certificate(certificate_id.clone(), String::from("plagiarism"))
+            .is_ok());
+        assert_eq!(
+            contract.verify_certificate(certificate_id.clone()),
+            Err(eduverse::Error::CertificateRevoked)
+        );
+        assert!(!contract.verify_completion(accounts.bob, course_id));
+        let record = contract
+            .get_certificate_revocation(certificate_id.clone())
+            .expect("Revocation should be recorded");
+        assert_eq!(record.revoked_by, accounts.charlie);
+        assert_eq!(record.revoked_at, 3500);
+
+        // A certificate can only be revoked once.
+        assert_eq!(
+            contract.revoke_certificate(certificate_id, String::from("again")),
+            Err(eduverse::Error::CertificateRevoked)
+        );
+    }
+
+    /// Test that completing a course twice does not mint a second certificate.
+    #[ink::test]
+    fn test_complete_course_twice() {
         let accounts = test::default_accounts::<DefaultEnvironment>();
         test::set_caller::<DefaultEnvironment>(accounts.alice);
         test::set_block_timestamp::<DefaultEnvironment>(1000);
+        let mut contract = Eduverse::new();
+        let course_id = create_test_course(&mut contract, MAX_STUDENTS);
+
+        test::set_caller::<DefaultEnvironment>(accounts.bob);
+        test::set_value_transferred::<DefaultEnvironment>(PRICE);
+        test::set_block_timestamp::<DefaultEnvironment>(1500);
+        assert!(contract.enroll(course_id).is_ok());
+        test::set_value_transferred::<DefaultEnvironment>(0);
+
+        test::set_caller::<DefaultEnvironment>(accounts.alice);
+        assert_eq!(contract.get_certificate_for(course_id, accounts.bob), None);
+        test::set_block_timestamp::<DefaultEnvironment>(3500);
+        assert!(contract.complete_course(course_id, accounts.bob).is_ok());
+
+        // Completing again at a later block is rejected instead of minting again.
+        test::set_block_timestamp::<DefaultEnvironment>(4000);
+        assert_eq!(
+            contract.complete_course(course_id, accounts.bob),
+            Err(eduverse::Error::AlreadyCompleted)
+        );
+
+        let certificates = contract.get_student_certificate_vector(accounts.bob);
+        assert_eq!(certificates.len(), 1);
+        assert_eq!(contract.balance_of(accounts.bob), 1);
+        assert_eq!(
+            contract.get_certificate_for(course_id, accounts.bob),
+            Some(certificates[0].clone())
+        );
+    }
 
+    /// Test completing a whole cohort in one call.
+    #[ink::test]
+    fn test_complete_course_batch() {
+        let accounts = test::default_accounts::<DefaultEnvironment>();
+        test::set_caller::<DefaultEnvironment>(accounts.alice);
+        test::set_block_timestamp::<DefaultEnvironment>(1000);
         let mut contract = Eduverse::new();
+        let course_id = create_test_course(&mut contract, MAX_STUDENTS);
 
-        let invalid_title = generate_string(101); // 101 characters
-        let description = String::from(COURSE_DESC);
-        let start_time = 2000;
-        let end_time = 3000;
-        let price = PRICE;
-        let metadata_hash = String::from(METADATA_HASH);
+        // Bob and Charlie enroll, Django does not.
+        test::set_block_timestamp::<DefaultEnvironment>(1500);
+        test::set_value_transferred::<DefaultEnvironment>(PRICE);
+        for student in [accounts.bob, accounts.charlie] {
+            test::set_caller::<DefaultEnvironment>(student);
+            assert!(contract.enroll(course_id).is_ok());
+        }
+        test::set_value_transferred::<DefaultEnvironment>(0);
 
-        let result = contract.create_course(
-            invalid_title,
-            description,
-            MAX_STUDENTS,
-            start_time,
-            end_time,
-            price,
-            metadata_hash,
+        test::set_caller::<DefaultEnvironment>(accounts.alice);
+        test::set_block_timestamp::<DefaultEnvironment>(3500);
+        assert!(contract
+            .complete_course(course_id, accounts.charlie)
+            .is_ok());
+
+        let results = contract
+            .complete_course_batch(
+                course_id,
+                Vec::from([accounts.bob, accounts.charlie, accounts.django]),
+            )
+            .expect("Batch completion should succeed");
+        let bob_certificate = contract
+            .get_certificate_for(course_id, accounts.bob)
+            .expect("Bob should hold a certificate");
+        assert_eq!(
+            results,
+            Vec::from([
+                eduverse::CompletionResult::Completed(bob_certificate),
+                eduverse::CompletionResult::AlreadyCompleted,
+                eduverse::CompletionResult::NotEnrolled,
+            ])
+        );
+        assert!(contract.verify_completion(accounts.bob, course_id));
+        assert!(!contract.verify_completion(accounts.django, course_id));
+    }
+
+    /// Test that oversized and unauthorized batches are rejected.
+    #[ink::test]
+    fn test_complete_course_batch_rejected() {
+        let accounts = test::default_accounts::<DefaultEnvironment>();
+        test::set_caller::<DefaultEnvironment>(accounts.alice);
+        test::set_block_timestamp::<DefaultEnvironment>(1000);
+        let mut contract = Eduverse::new();
+        let course_id = create_test_course(&mut contract, MAX_STUDENTS);
+        test::set_block_timestamp::<DefaultEnvironment>(3500);
+
+        let too_many = Vec::from([accounts.bob; eduverse::MAX_COMPLETION_BATCH + 1]);
+        assert_eq!(
+            contract.complete_course_batch(course_id, too_many),
+            Err(eduverse::Error::BatchTooLarge)
+        );
+
+        test::set_caller::<DefaultEnvironment>(accounts.bob);
+        assert_eq!(
+            contract.complete_course_batch(course_id, Vec::from([accounts.bob])),
+            Err(eduverse::Error::Unauthorized)
+        );
+    }
+
+    /// Test the two step ownership transfer.
+    #[ink::test]
+    fn test_transfer_ownership() {
+        let accounts = test::default_accounts::<DefaultEnvironment>();
+        test::set_caller::<DefaultEnvironment>(accounts.alice);
+        let mut contract = Eduverse::new();
+
+        // Only the owner can nominate a successor.
+        test::set_caller::<DefaultEnvironment>(accounts.bob);
+        assert_eq!(
+            contract.transfer_ownership(accounts.bob),
+            Err(eduverse::Error::NotOwner)
+        );
+
+        test::set_caller::<DefaultEnvironment>(accounts.alice);
+        assert!(contract.transfer_ownership(accounts.bob).is_ok());
+        assert_eq!(contract.get_pending_owner(), Some(accounts.bob));
+        assert_eq!(contract.get_owner(), accounts.alice);
+
+        // Only the nominee can accept.
+        test::set_caller::<DefaultEnvironment>(accounts.charlie);
+        assert_eq!(
+            contract.accept_ownership(),
+            Err(eduverse::Error::Unauthorized)
+        );
+
+        test::set_caller::<DefaultEnvironment>(accounts.bob);
+        assert!(contract.accept_ownership().is_ok());
+        assert_eq!(contract.get_owner(), accounts.bob);
+        assert_eq!(contract.get_pending_owner(), None);
+
+        // Alice no longer has admin powers.
+        test::set_caller::<DefaultEnvironment>(accounts.alice);
+        assert_eq!(contract.pause(), Err(eduverse::Error::NotOwner));
+    }
+
+    /// Test that pausing blocks course creation, enrollment and completion but not views.
+    #[ink::test]
+    fn test_pause_and_unpause() {
+        let accounts = test::default_accounts::<DefaultEnvironment>();
+        test::set_caller::<DefaultEnvironment>(accounts.alice);
+        test::set_block_timestamp::<DefaultEnvironment>(1000);
+        let mut contract = Eduverse::new();
+        let course_id = create_test_course(&mut contract, MAX_STUDENTS);
+
+        assert!(contract.pause().is_ok());
+        assert!(contract.is_paused());
+        assert_eq!(contract.pause(), Err(eduverse::Error::ContractPaused));
+
+        assert_eq!(
+            contract.create_course(
+                String::from(COURSE_TITLE),
+                String::from(COURSE_DESC),
+                MAX_STUDENTS,
+                2000,
+                3000,
+                PRICE,
+                String::from(METADATA_HASH),
+                REFUND_WINDOW,
+                None,
+                Vec::new(),
+            ),
+            Err(eduverse::Error::ContractPaused)
+        );
+
+        test::set_caller::<DefaultEnvironment>(accounts.bob);
+        test::set_value_transferred::<DefaultEnvironment>(PRICE);
+        test::set_block_timestamp::<DefaultEnvironment>(1500);
+        assert_eq!(
+            contract.enroll(course_id),
+            Err(eduverse::Error::ContractPaused)
+        );
+        test::set_value_transferred::<DefaultEnvironment>(0);
+
+        test::set_caller::<DefaultEnvironment>(accounts.alice);
+        assert_eq!(
+            contract.complete_course(course_id, accounts.bob),
+            Err(eduverse::Error::ContractPaused)
+        );
+
+        // So are course changes, refunds and payouts, leaving storage to migrations.
+        assert_eq!(
+            contract.cancel_course(course_id, String::from("reason")),
+            Err(eduverse::Error::ContractPaused)
+        );
+        assert_eq!(
+            contract.release_payments(course_id),
+            Err(eduverse::Error::ContractPaused)
+        );
+        assert_eq!(
+            contract.withdraw(PRICE),
+            Err(eduverse::Error::ContractPaused)
+        );
+        test::set_caller::<DefaultEnvironment>(accounts.bob);
+        assert_eq!(
+            contract.request_refund(course_id),
+            Err(eduverse::Error::ContractPaused)
+        );
+        assert_eq!(
+            contract.leave_waitlist(course_id),
+            Err(eduverse::Error::ContractPaused)
+        );
+
+        // Views keep working.
+        assert!(contract.get_course(course_id).is_some());
+        assert_eq!(contract.get_courses().len(), 1);
+
+        assert!(contract.unpause().is_ok());
+        assert_eq!(contract.unpause(), Err(eduverse::Error::NotPaused));
+
+        test::set_caller::<DefaultEnvironment>(accounts.bob);
+        test::set_value_transferred::<DefaultEnvironment>(PRICE);
+        assert!(contract.enroll(course_id).is_ok());
+    }
+
+    /// Test granting and revoking course roles and the powers they carry.
+    #[ink::test]
+    fn test_course_roles() {
+        let accounts = test::default_accounts::<DefaultEnvironment>();
+        test::set_caller::<DefaultEnvironment>(accounts.alice);
+        test::set_block_timestamp::<DefaultEnvironment>(1000);
+        let mut contract = Eduverse::new();
+        let course_id = create_test_course(&mut contract, MAX_STUDENTS);
+        let assignment_id = contract
+            .add_assignment(course_id, String::from("Essay"), 2500, 10, 1)
+            .unwrap();
+
+        // Only the teacher can grant roles.
+        test::set_caller::<DefaultEnvironment>(accounts.charlie);
+        assert_eq!(
+            contract.grant_role(course_id, accounts.charlie, eduverse::CourseRole::CoTeacher),
+            Err(eduverse::Error::Unauthorized)
+        );
+
+        test::set_caller::<DefaultEnvironment>(accounts.alice);
+        assert!(contract
+            .grant_role(course_id, accounts.charlie, eduverse::CourseRole::CoTeacher)
+            .is_ok());
+        assert!(contract
+            .grant_role(course_id, accounts.django, eduverse::CourseRole::Grader)
+            .is_ok());
+        assert!(contract
+            .grant_role(
+                course_id,
+                accounts.frank,
+                eduverse::CourseRole::TeachingAssistant
+            )
+            .is_ok());
+        assert_eq!(
+            contract.get_course_roles(course_id),
+            Vec::from([
+                (accounts.charlie, eduverse::CourseRole::CoTeacher),
+                (accounts.django, eduverse::CourseRole::Grader),
+                (accounts.frank, eduverse::CourseRole::TeachingAssistant),
+            ])
+        );
+
+        // The co-teacher can update the course, the grader cannot.
+        test::set_caller::<DefaultEnvironment>(accounts.charlie);
+        assert!(contract
+            .update_course(
+                course_id,
+                Some(String::from("New Title")),
+                None,
+                None,
+                None,
+                None,
+                None,
+                None
+            )
+            .is_ok());
+        test::set_caller::<DefaultEnvironment>(accounts.django);
+        assert_eq!(
+            contract.update_course(course_id, None, None, None, None, Some(false), None, None),
+            Err(eduverse::Error::Unauthorized)
+        );
+
+        // Bob enrolls and the grader marks the completion after the course ends.
+        test::set_caller::<DefaultEnvironment>(accounts.bob);
+        test::set_value_transferred::<DefaultEnvironment>(PRICE);
+        test::set_block_timestamp::<DefaultEnvironment>(1500);
+        assert!(contract.enroll(course_id).is_ok());
+        test::set_value_transferred::<DefaultEnvironment>(0);
+
+        // The teaching assistant grades Bob's essay but cannot complete Bob.
+        test::set_block_timestamp::<DefaultEnvironment>(2400);
+        assert!(contract
+            .submit_assignment(course_id, assignment_id, String::from("essay_hash"))
+            .is_ok());
+        test::set_caller::<DefaultEnvironment>(accounts.frank);
+        assert!(contract
+            .grade_submission(course_id, assignment_id, accounts.bob, 9)
+            .is_ok());
+        test::set_block_timestamp::<DefaultEnvironment>(3500);
+        assert_eq!(
+            contract.complete_course(course_id, accounts.bob),
+            Err(eduverse::Error::Unauthorized)
+        );
+
+        test::set_caller::<DefaultEnvironment>(accounts.django);
+        assert!(contract.complete_course(course_id, accounts.bob).is_ok());
+
+        // Revoked roles lose their powers.
+        test::set_caller::<DefaultEnvironment>(accounts.alice);
+        assert!(contract.revoke_role(course_id, accounts.django).is_ok());
+        assert_eq!(
+            contract.revoke_role(course_id, accounts.django),
+            Err(eduverse::Error::RoleNotFound)
+        );
+        assert_eq!(
+            contract.get_course_roles(course_id),
+            Vec::from([
+                (accounts.charlie, eduverse::CourseRole::CoTeacher),
+                (accounts.frank, eduverse::CourseRole::TeachingAssistant),
+            ])
+        );
+    }
+
+    /// Test splitting released revenue between co-instructors.
+    #[ink::test]
+    fn test_revenue_split() {
+        let accounts = test::default_accounts::<DefaultEnvironment>();
+        test::set_caller::<DefaultEnvironment>(accounts.alice);
+        test::set_block_timestamp::<DefaultEnvironment>(1000);
+        let mut contract = Eduverse::new();
+        let course_id = create_test_course(&mut contract, MAX_STUDENTS);
+
+        // Shares must add up to 100%.
+        assert_eq!(
+            contract.set_course_payees(
+                course_id,
+                Vec::from([
+                    eduverse::Payee {
+                        account: accounts.alice,
+                        share_bps: 7_000
+                    },
+                    eduverse::Payee {
+                        account: accounts.charlie,
+                        share_bps: 2_000
+                    },
+                ])
+            ),
+            Err(eduverse::Error::InvalidRevenueShares)
+        );
+
+        // Payees are listed once and never with an empty share.
+        for payees in [
+            [(accounts.alice, 10_000), (accounts.charlie, 0)],
+            [(accounts.alice, 5_000), (accounts.alice, 5_000)],
+        ] {
+            let payees = payees
+                .iter()
+                .map(|(account, share_bps)| eduverse::Payee {
+                    account: *account,
+                    share_bps: *share_bps,
+                })
+                .collect();
+            assert_eq!(
+                contract.set_course_payees(course_id, payees),
+                Err(eduverse::Error::InvalidRevenueShares)
+            );
+        }
+
+        assert!(contract
+            .set_course_payees(
+                course_id,
+                Vec::from([
+                    eduverse::Payee {
+                        account: accounts.alice,
+                        share_bps: 7_000
+                    },
+                    eduverse::Payee {
+                        account: accounts.charlie,
+                        share_bps: 3_000
+                    },
+                ])
+            )
+            .is_ok());
+
+        test::set_caller::<DefaultEnvironment>(accounts.bob);
+        test::set_value_transferred::<DefaultEnvironment>(PRICE);
+        test::set_block_timestamp::<DefaultEnvironment>(1500);
+        assert!(contract.enroll(course_id).is_ok());
+        test::set_value_transferred::<DefaultEnvironment>(0);
+
+        test::set_block_timestamp::<DefaultEnvironment>(2500);
+        assert!(contract.release_payments(course_id).is_ok());
+        assert_eq!(contract.get_pending_withdrawal(accounts.alice), 70);
+        assert_eq!(contract.get_pending_withdrawal(accounts.charlie), 30);
+    }
+
+    /// Test that upgrades and migrations are restricted to the owner.
+    #[ink::test]
+    fn test_upgrade_and_migrate_owner_only() {
+        let accounts = test::default_accounts::<DefaultEnvironment>();
+        test::set_caller::<DefaultEnvironment>(accounts.alice);
+        let mut contract = Eduverse::new();
+        assert_eq!(contract.get_storage_version(), eduverse::STORAGE_VERSION);
+
+        // Migrations need a paused contract, whose storage is already current.
+        assert_eq!(contract.migrate(), Err(eduverse::Error::NotPaused));
+        assert!(contract.pause().is_ok());
+        assert_eq!(contract.migrate(), Err(eduverse::Error::NothingToMigrate));
+
+        test::set_caller::<DefaultEnvironment>(accounts.bob);
+        assert_eq!(
+            contract.upgrade(Default::default()),
+            Err(eduverse::Error::NotOwner)
+        );
+        assert_eq!(contract.migrate(), Err(eduverse::Error::NotOwner));
+    }
+
+    /// Test paging through courses with filters.
+    #[ink::test]
+    fn test_get_courses_paged() {
+        let accounts = test::default_accounts::<DefaultEnvironment>();
+        test::set_caller::<DefaultEnvironment>(accounts.alice);
+        test::set_block_timestamp::<DefaultEnvironment>(1000);
+        let mut contract = Eduverse::new();
+
+        // Alice creates three paid courses, Bob one free course.
+        for _ in 0..3 {
+            create_test_course(&mut contract, MAX_STUDENTS);
+        }
+        test::set_caller::<DefaultEnvironment>(accounts.bob);
+        let free_course = contract
+            .create_course(
+                String::from("Free Course"),
+                String::from(COURSE_DESC),
+                MAX_STUDENTS,
+                2000,
+                3000,
+                0,
+                String::from(METADATA_HASH),
+                REFUND_WINDOW,
+                None,
+                Vec::new(),
+            )
+            .expect("Course creation should succeed");
+
+        // Unfiltered pages of two.
+        let first = contract.get_courses_paged(0, 2, eduverse::CourseFilter::default());
+        assert_eq!(first.courses.len(), 2);
+        assert_eq!(first.next_cursor, Some(2));
+        let second = contract.get_courses_paged(2, 2, eduverse::CourseFilter::default());
+        assert_eq!(second.courses.len(), 2);
+        assert_eq!(second.next_cursor, None);
+
+        // A zero limit still moves the cursor on.
+        let single = contract.get_courses_paged(0, 0, eduverse::CourseFilter::default());
+        assert_eq!(single.courses.len(), 1);
+        assert_eq!(single.next_cursor, Some(1));
+
+        // Free courses only.
+        let free = contract.get_courses_paged(
+            0,
+            10,
+            eduverse::CourseFilter {
+                free_only: true,
+                ..Default::default()
+            },
+        );
+        assert_eq!(free.courses.len(), 1);
+        assert_eq!(free.courses[0].id, free_course);
+
+        // By teacher and price range.
+        let alice_courses = contract.get_courses_paged(
+            0,
+            10,
+            eduverse::CourseFilter {
+                teacher: Some(accounts.alice),
+                min_price: Some(PRICE),
+                max_price: Some(PRICE),
+                ..Default::default()
+            },
+        );
+        assert_eq!(alice_courses.courses.len(), 3);
+
+        // Upcoming only once the courses have started.
+        test::set_block_timestamp::<DefaultEnvironment>(2000);
+        let upcoming = contract.get_courses_paged(
+            0,
+            10,
+            eduverse::CourseFilter {
+                upcoming_only: true,
+                ..Default::default()
+            },
+        );
+        assert!(upcoming.courses.is_empty());
+    }
+
+    /// Test that unenrolling keeps the enrollment indexes consistent.
+    #[ink::test]
+    fn test_enrollment_indexes_after_unenroll() {
+        let accounts = test::default_accounts::<DefaultEnvironment>();
+        test::set_caller::<DefaultEnvironment>(accounts.alice);
+        test::set_block_timestamp::<DefaultEnvironment>(1000);
+        let mut contract = Eduverse::new();
+        let first_course = create_test_course(&mut contract, MAX_STUDENTS);
+        let second_course = create_test_course(&mut contract, MAX_STUDENTS);
+
+        for student in [accounts.bob, accounts.charlie, accounts.django] {
+            test::set_caller::<DefaultEnvironment>(student);
+            test::set_value_transferred::<DefaultEnvironment>(PRICE);
+            assert!(contract.enroll(first_course).is_ok());
+        }
+        test::set_caller::<DefaultEnvironment>(accounts.bob);
+        test::set_value_transferred::<DefaultEnvironment>(PRICE);
+        assert!(contract.enroll(second_course).is_ok());
+
+        // Bob leaves the first course, the last student takes the free slot.
+        assert!(contract.unenroll(first_course).is_ok());
+        assert!(!contract.verify_enrollment(accounts.bob, first_course));
+        assert_eq!(
+            contract.get_course_students(first_course),
+            vec![accounts.django, accounts.charlie]
+        );
+        assert_eq!(
+            contract.get_student_courses(accounts.bob),
+            vec![second_course]
+        );
+
+        // Charlie can still leave after being moved around.
+        test::set_caller::<DefaultEnvironment>(accounts.charlie);
+        assert!(contract.unenroll(first_course).is_ok());
+        assert_eq!(
+            contract.get_course_students(first_course),
+            vec![accounts.django]
         );
-        assert_eq!(result, Err(eduverse::Error::InvalidInput));
+        assert!(contract.get_student_courses(accounts.charlie).is_empty());
     }
 
-    /// Test creating a course with an invalid description length (more than 1000 characters).
+    /// Test migrating version 1 enrollment lists into the indexes.
     #[ink::test]
-    fn test_create_course_invalid_description() {
+    fn test_migrate_v1_enrollments() {
         let accounts = test::default_accounts::<DefaultEnvironment>();
         test::set_caller::<DefaultEnvironment>(accounts.alice);
         test::set_block_timestamp::<DefaultEnvironment>(1000);
-
         let mut contract = Eduverse::new();
+        let course_id = create_test_course(&mut contract, MAX_STUDENTS);
+        let course = contract.get_course(course_id).unwrap();
+
+        contract.insert_legacy_course(&course, 1);
+        contract.insert_v1_enrollment(course_id, accounts.bob);
+        contract.insert_v1_enrollment(course_id, accounts.charlie);
+        contract.set_storage_version(1);
+        assert!(!contract.verify_enrollment(accounts.bob, course_id));
+
+        // Storage is only migrated while the contract is paused.
+        assert_eq!(contract.migrate(), Err(eduverse::Error::NotPaused));
+        assert!(contract.pause().is_ok());
+
+        // One call per layout step, each moving the version up by one.
+        assert!(contract.migrate().is_ok());
+        assert_eq!(contract.get_storage_version(), 2);
+        while contract.get_storage_version() < eduverse::STORAGE_VERSION {
+            assert!(contract.migrate().is_ok());
+        }
+        assert!(contract.verify_enrollment(accounts.bob, course_id));
+        assert_eq!(
+            contract.get_course_students(course_id),
+            vec![accounts.bob, accounts.charlie]
+        );
+        assert_eq!(
+            contract.get_student_courses(accounts.charlie),
+            vec![course_id]
+        );
+        let record = contract.get_enrollment(course_id, accounts.bob).unwrap();
+        assert_eq!(record.amount_paid, PRICE);
+        assert_eq!(record.status, eduverse::EnrollmentStatus::Active);
+        assert_eq!(contract.get_course(course_id), Some(course));
+        assert_eq!(contract.migrate(), Err(eduverse::Error::NothingToMigrate));
+    }
 
-        let title = String::from(COURSE_TITLE);
-        let invalid_description = generate_string(1001); // 1001 characters
-        let start_time = 2000;
-        let end_time = 3000;
-        let price = PRICE;
-        let metadata_hash = String::from(METADATA_HASH);
+    /// Test that courses stored before `prerequisites` existed decode after migrating.
+    #[ink::test]
+    fn test_migrate_v3_courses() {
+        assert_migrates_courses_from(3);
+    }
 
-        let result = contract.create_course(
-            title,
-            invalid_description,
-            MAX_STUDENTS,
-            start_time,
-            end_time,
-            price,
-            metadata_hash,
-        );
-        assert_eq!(result, Err(eduverse::Error::InvalidInput));
+    /// Test that courses stored before `completion_rule` existed decode after migrating.
+    #[ink::test]
+    fn test_migrate_v4_courses() {
+        assert_migrates_courses_from(4);
     }
 
-    /// Test creating a course with invalid timing (start_time in the past).
+    /// Test that completion rules stored before `min_attendance` existed decode after migrating.
     #[ink::test]
-    fn test_create_course_invalid_time_start() {
+    fn test_migrate_v5_courses() {
+        assert_migrates_courses_from(5);
+    }
+
+    /// Test enrollment records following the student through each status.
+    #[ink::test]
+    fn test_enrollment_record_transitions() {
         let accounts = test::default_accounts::<DefaultEnvironment>();
         test::set_caller::<DefaultEnvironment>(accounts.alice);
-        // current block time is 1000
         test::set_block_timestamp::<DefaultEnvironment>(1000);
-
         let mut contract = Eduverse::new();
+        let course_id = create_test_course(&mut contract, MAX_STUDENTS);
 
-        let title = String::from(COURSE_TITLE);
-        let description = String::from(COURSE_DESC);
-        let start_time = 500; // Invalid: before current time
-        let end_time = 3000;
-        let price = PRICE;
-        let metadata_hash = String::from(METADATA_HASH);
+        test::set_block_timestamp::<DefaultEnvironment>(1500);
+        for student in [accounts.bob, accounts.charlie, accounts.django] {
+            test::set_caller::<DefaultEnvironment>(student);
+            test::set_value_transferred::<DefaultEnvironment>(PRICE);
+            assert!(contract.enroll(course_id).is_ok());
+        }
+        assert_eq!(
+            contract.get_enrollment(course_id, accounts.bob),
+            Some(eduverse::EnrollmentRecord {
+                enrolled_at: 1500,
+                amount_paid: PRICE,
+                payment_asset: None,
+                status: eduverse::EnrollmentStatus::Active,
+            })
+        );
 
-        let result = contract.create_course(
-            title,
-            description,
-            MAX_STUDENTS,
-            start_time,
-            end_time,
-            price,
-            metadata_hash,
+        // Django leaves before the start, the record stays behind.
+        test::set_caller::<DefaultEnvironment>(accounts.django);
+        assert!(contract.unenroll(course_id).is_ok());
+        assert_eq!(
+            contract
+                .get_enrollment(course_id, accounts.django)
+                .unwrap()
+                .status,
+            eduverse::EnrollmentStatus::Dropped
+        );
+
+        // After the end Alice completes Bob and fails Charlie.
+        test::set_caller::<DefaultEnvironment>(accounts.alice);
+        test::set_block_timestamp::<DefaultEnvironment>(3500);
+        assert!(contract.complete_course(course_id, accounts.bob).is_ok());
+        assert!(contract.fail_student(course_id, accounts.charlie).is_ok());
+        assert_eq!(
+            contract
+                .get_enrollment(course_id, accounts.bob)
+                .unwrap()
+                .status,
+            eduverse::EnrollmentStatus::Completed
+        );
+        assert_eq!(
+            contract
+                .get_enrollment(course_id, accounts.charlie)
+                .unwrap()
+                .status,
+            eduverse::EnrollmentStatus::Failed
+        );
+
+        // A failed student can no longer be completed or failed again.
+        assert_eq!(
+            contract.complete_course(course_id, accounts.charlie),
+            Err(eduverse::Error::EnrollmentNotActive)
+        );
+        assert_eq!(
+            contract.fail_student(course_id, accounts.bob),
+            Err(eduverse::Error::EnrollmentNotActive)
+        );
+        assert_eq!(
+            contract.fail_student(course_id, accounts.django),
+            Err(eduverse::Error::NotEnrolled)
         );
-        assert_eq!(result, Err(eduverse::Error::InvalidTime));
     }
 
-    /// Test creating a course with invalid timing (end_time <= start_time).
+    /// Test waitlisted students being promoted as seats free up.
     #[ink::test]
-    fn test_create_course_invalid_time_end() {
+    fn test_waitlist_promotion() {
         let accounts = test::default_accounts::<DefaultEnvironment>();
         test::set_caller::<DefaultEnvironment>(accounts.alice);
-        // current block time is 1000
         test::set_block_timestamp::<DefaultEnvironment>(1000);
-
         let mut contract = Eduverse::new();
+        let course_id = create_test_course(&mut contract, 1);
 
-        let title = String::from(COURSE_TITLE);
-        let description = String::from(COURSE_DESC);
-        let start_time = 2000;
-        let end_time = 1500; // Invalid: end_time before start_time
-        let price = PRICE;
-        let metadata_hash = String::from(METADATA_HASH);
+        // The only seat is free, so there is nothing to wait for yet.
+        test::set_caller::<DefaultEnvironment>(accounts.charlie);
+        test::set_value_transferred::<DefaultEnvironment>(PRICE);
+        assert_eq!(
+            contract.join_waitlist(course_id, true),
+            Err(eduverse::Error::CourseNotFull)
+        );
 
-        let result = contract.create_course(
-            title,
-            description,
-            MAX_STUDENTS,
-            start_time,
-            end_time,
-            price,
-            metadata_hash,
+        test::set_caller::<DefaultEnvironment>(accounts.bob);
+        assert!(contract.enroll(course_id).is_ok());
+
+        // Charlie and Django queue up with the price in escrow.
+        for student in [accounts.charlie, accounts.django] {
+            test::set_caller::<DefaultEnvironment>(student);
+            assert!(contract.join_waitlist(course_id, true).is_ok());
+        }
+        assert_eq!(
+            contract.join_waitlist(course_id, true),
+            Err(eduverse::Error::AlreadyWaitlisted)
         );
-        assert_eq!(result, Err(eduverse::Error::InvalidTime));
+        assert_eq!(
+            contract.get_waitlist(course_id),
+            vec![accounts.charlie, accounts.django]
+        );
+
+        // Bob leaves and Charlie takes the seat with the escrowed payment.
+        test::set_caller::<DefaultEnvironment>(accounts.bob);
+        test::set_value_transferred::<DefaultEnvironment>(0);
+        assert!(contract.unenroll(course_id).is_ok());
+        assert!(contract.verify_enrollment(accounts.charlie, course_id));
+        assert_eq!(
+            contract.get_escrowed_payment(course_id, accounts.charlie),
+            PRICE
+        );
+        assert!(contract
+            .get_waitlist_entry(course_id, accounts.charlie)
+            .is_none());
+        assert_eq!(contract.get_waitlist(course_id), vec![accounts.django]);
+
+        // Raising the capacity promotes Django as well.
+        test::set_caller::<DefaultEnvironment>(accounts.alice);
+        assert!(contract
+            .update_course(course_id, None, None, Some(3), None, None, None, None)
+            .is_ok());
+        assert!(contract.verify_enrollment(accounts.django, course_id));
+        assert!(contract.get_waitlist(course_id).is_empty());
+        assert_eq!(contract.get_course(course_id).unwrap().enrolled_count, 2);
     }
 
-    /// Test enrolling a student with insufficient payment.
+    /// Test leaving the waitlist after the course started.
     #[ink::test]
-    fn test_enroll_insufficient_payment() {
+    fn test_leave_waitlist_after_start() {
         let accounts = test::default_accounts::<DefaultEnvironment>();
-        // Create course as Alice.
         test::set_caller::<DefaultEnvironment>(accounts.alice);
         test::set_block_timestamp::<DefaultEnvironment>(1000);
         let mut contract = Eduverse::new();
+        let course_id = create_test_course(&mut contract, 1);
 
-        let title = String::from("Insufficient Payment Course");
-        let description = String::from("Test insufficient payment.");
-        let start_time = 2000;
-        let end_time = 3000;
-        let price = PRICE;
-        let metadata_hash = String::from("insuff_hash");
+        test::set_value_transferred::<DefaultEnvironment>(PRICE);
+        test::set_caller::<DefaultEnvironment>(accounts.bob);
+        assert!(contract.enroll(course_id).is_ok());
+        test::set_caller::<DefaultEnvironment>(accounts.charlie);
+        assert!(contract.join_waitlist(course_id, true).is_ok());
+        assert_eq!(
+            contract
+                .get_waitlist_entry(course_id, accounts.charlie)
+                .unwrap()
+                .escrowed,
+            PRICE
+        );
 
-        let course_id = contract
+        // Charlie was never promoted and takes the escrow back.
+        test::set_value_transferred::<DefaultEnvironment>(0);
+        test::set_block_timestamp::<DefaultEnvironment>(2500);
+        assert!(contract.leave_waitlist(course_id).is_ok());
+        assert!(contract.get_waitlist(course_id).is_empty());
+        assert!(!contract.verify_enrollment(accounts.charlie, course_id));
+        assert_eq!(
+            contract.leave_waitlist(course_id),
+            Err(eduverse::Error::NotWaitlisted)
+        );
+    }
+
+    /// Test that enrollment requires completed prerequisites.
+    #[ink::test]
+    fn test_enroll_requires_prerequisites() {
+        let accounts = test::default_accounts::<DefaultEnvironment>();
+        test::set_caller::<DefaultEnvironment>(accounts.alice);
+        test::set_block_timestamp::<DefaultEnvironment>(1000);
+        let mut contract = Eduverse::new();
+        let basics = create_test_course(&mut contract, MAX_STUDENTS);
+
+        // Prerequisites must point at earlier courses.
+        assert_eq!(
+            contract.update_course(
+                basics,
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+                Some(vec![basics])
+            ),
+            Err(eduverse::Error::InvalidInput)
+        );
+        let advanced = contract
             .create_course(
-                title,
-                description,
+                String::from("Advanced"),
+                String::from(COURSE_DESC),
                 MAX_STUDENTS,
-                start_time,
-                end_time,
-                price,
-                metadata_hash,
+                4000,
+                5000,
+                PRICE,
+                String::from(METADATA_HASH),
+                REFUND_WINDOW,
+                None,
+                vec![basics],
             )
-            .expect("Course creation should succeed");
+            .unwrap();
+        assert_eq!(
+            contract.get_course(advanced).unwrap().prerequisites,
+            vec![basics]
+        );
 
-        // Bob attempts to enroll with insufficient funds.
+        // Bob has not completed the basics yet.
         test::set_caller::<DefaultEnvironment>(accounts.bob);
-        // Transferred value less than price.
-        test::set_value_transferred::<DefaultEnvironment>(PRICE - 1);
-        test::set_block_timestamp::<DefaultEnvironment>(1500);
+        test::set_value_transferred::<DefaultEnvironment>(PRICE);
+        assert_eq!(
+            contract.check_eligibility(advanced, accounts.bob),
+            Err(eduverse::Error::MissingPrerequisite(basics))
+        );
+        assert_eq!(
+            contract.enroll(advanced),
+            Err(eduverse::Error::MissingPrerequisite(basics))
+        );
 
-        let enroll_result = contract.enroll(course_id);
-        assert_eq!(enroll_result, Err(eduverse::Error::InsufficientPayment));
+        // Once the basics are completed Bob is eligible.
+        assert!(contract.enroll(basics).is_ok());
+        test::set_caller::<DefaultEnvironment>(accounts.alice);
+        test::set_block_timestamp::<DefaultEnvironment>(3500);
+        assert!(contract.complete_course(basics, accounts.bob).is_ok());
+        assert_eq!(contract.check_eligibility(advanced, accounts.bob), Ok(()));
+        test::set_caller::<DefaultEnvironment>(accounts.bob);
+        assert!(contract.enroll(advanced).is_ok());
     }
 
-    /// Test enrolling a student when the course is not active.
+    /// Test lessons being added by the teacher and completed by students.
     #[ink::test]
-    fn test_enroll_course_not_active() {
+    fn test_lesson_progress() {
         let accounts = test::default_accounts::<DefaultEnvironment>();
-        // Create a course as Alice.
         test::set_caller::<DefaultEnvironment>(accounts.alice);
         test::set_block_timestamp::<DefaultEnvironment>(1000);
         let mut contract = Eduverse::new();
+        let course_id = create_test_course(&mut contract, MAX_STUDENTS);
+
+        // Alice sets up two modules, the second lesson unlocks at the start.
+        for (module, unlock_time) in [(0, None), (0, Some(2000)), (1, None), (1, None)] {
+            assert!(contract
+                .add_lesson(
+                    course_id,
+                    module,
+                    String::from("Lesson"),
+                    String::from(METADATA_HASH),
+                    unlock_time
+                )
+                .is_ok());
+        }
+        assert_eq!(contract.get_lessons(course_id).len(), 4);
 
-        let title = String::from("Inactive Course");
-        let description = String::from("Test course inactive.");
-        let start_time = 2000;
-        let end_time = 3000;
-        let price = PRICE;
-        let metadata_hash = String::from("inactive_hash");
+        // Only the teacher side manages lessons.
+        test::set_caller::<DefaultEnvironment>(accounts.bob);
+        assert_eq!(
+            contract.update_lesson(course_id, 0, Some(String::from("Mine")), None, None),
+            Err(eduverse::Error::Unauthorized)
+        );
+        assert_eq!(
+            contract.mark_lesson_complete(course_id, 0),
+            Err(eduverse::Error::NotEnrolled)
+        );
 
-        let course_id = contract
-            .create_course(
-                title,
-                description,
-                MAX_STUDENTS,
-                start_time,
-                end_time,
-                price,
-                metadata_hash,
-            )
-            .expect("Course creation should succeed");
+        test::set_value_transferred::<DefaultEnvironment>(PRICE);
+        assert!(contract.enroll(course_id).is_ok());
+        assert!(contract.mark_lesson_complete(course_id, 0).is_ok());
+        assert_eq!(
+            contract.mark_lesson_complete(course_id, 0),
+            Err(eduverse::Error::LessonAlreadyCompleted)
+        );
+        assert_eq!(
+            contract.mark_lesson_complete(course_id, 1),
+            Err(eduverse::Error::LessonLocked)
+        );
+        assert_eq!(
+            contract.mark_lesson_complete(course_id, 4),
+            Err(eduverse::Error::LessonNotFound)
+        );
+        assert_eq!(contract.get_lesson_progress(course_id, accounts.bob), 25);
 
-        // Update the course to set active = false.
-        let update_result =
-            contract.update_course(course_id, None, None, None, None, Some(false), None);
-        assert!(update_result.is_ok());
+        test::set_block_timestamp::<DefaultEnvironment>(2000);
+        assert!(contract.mark_lesson_complete(course_id, 1).is_ok());
+        assert!(contract.is_lesson_completed(course_id, accounts.bob, 1));
+        assert_eq!(contract.get_lesson_progress(course_id, accounts.bob), 50);
 
-        // Bob attempts to enroll.
+        // Progress stops counting once Alice failed Bob.
+        test::set_caller::<DefaultEnvironment>(accounts.alice);
+        test::set_block_timestamp::<DefaultEnvironment>(3000);
+        assert!(contract.fail_student(course_id, accounts.bob).is_ok());
         test::set_caller::<DefaultEnvironment>(accounts.bob);
-        test::set_value_transferred::<DefaultEnvironment>(price);
-        test::set_block_timestamp::<DefaultEnvironment>(1500);
-
-        let enroll_result = contract.enroll(course_id);
-        assert_eq!(enroll_result, Err(eduverse::Error::CourseNotActive));
+        assert_eq!(
+            contract.mark_lesson_complete(course_id, 2),
+            Err(eduverse::Error::EnrollmentNotActive)
+        );
     }
 
-    /// Test enrolling a student when the course is already in progress.
+    /// Test submitting and grading assignments into an aggregate score.
     #[ink::test]
-    fn test_enroll_course_in_progress() {
+    fn test_assignment_grading() {
         let accounts = test::default_accounts::<DefaultEnvironment>();
-        // Create a course as Alice.
         test::set_caller::<DefaultEnvironment>(accounts.alice);
         test::set_block_timestamp::<DefaultEnvironment>(1000);
         let mut contract = Eduverse::new();
+        let course_id = create_test_course(&mut contract, MAX_STUDENTS);
 
-        let title = String::from("In Progress Course");
-        let description = String::from("Test course in progress.");
-        let start_time = 2000;
-        let end_time = 3000;
-        let price = PRICE;
-        let metadata_hash = String::from("inprog_hash");
+        // A quiz worth one part and a project worth three, weightless work is rejected.
+        assert_eq!(
+            contract.add_assignment(course_id, String::from("Reading"), 2500, 10, 0),
+            Err(eduverse::Error::InvalidInput)
+        );
+        let quiz = contract
+            .add_assignment(course_id, String::from("Quiz"), 2500, 10, 1)
+            .unwrap();
+        let project = contract
+            .add_assignment(course_id, String::from("Project"), 2800, 100, 3)
+            .unwrap();
+        assert!(contract
+            .grant_role(course_id, accounts.charlie, eduverse::CourseRole::Grader)
+            .is_ok());
 
-        let course_id = contract
-            .create_course(
-                title,
-                description,
-                MAX_STUDENTS,
-                start_time,
-                end_time,
-                price,
-                metadata_hash,
-            )
-            .expect("Course creation should succeed");
+        test::set_caller::<DefaultEnvironment>(accounts.bob);
+        test::set_value_transferred::<DefaultEnvironment>(PRICE);
+        assert!(contract.enroll(course_id).is_ok());
+
+        // The quiz is on time, the project comes in after its deadline.
+        test::set_block_timestamp::<DefaultEnvironment>(2400);
+        assert!(contract
+            .submit_assignment(course_id, quiz, String::from("quiz_hash"))
+            .is_ok());
+        test::set_block_timestamp::<DefaultEnvironment>(2900);
+        assert!(contract
+            .submit_assignment(course_id, project, String::from("project_hash"))
+            .is_ok());
+        assert!(
+            contract
+                .get_submission(course_id, project, accounts.bob)
+                .unwrap()
+                .late
+        );
 
-        // Bob attempts to enroll after the course has started.
+        // Students cannot grade themselves, the grader can.
+        assert_eq!(
+            contract.grade_submission(course_id, quiz, accounts.bob, 10),
+            Err(eduverse::Error::Unauthorized)
+        );
+        test::set_caller::<DefaultEnvironment>(accounts.charlie);
+        assert_eq!(
+            contract.grade_submission(course_id, quiz, accounts.bob, 11),
+            Err(eduverse::Error::InvalidInput)
+        );
+        assert!(contract
+            .grade_submission(course_id, quiz, accounts.bob, 8)
+            .is_ok());
+        assert!(contract
+            .grade_submission(course_id, project, accounts.bob, 50)
+            .is_ok());
+
+        // (80% * 1 + 50% * 3) / 4 = 57.5%
+        assert_eq!(contract.get_aggregate_score(course_id, accounts.bob), 5_750);
+
+        // Graded work cannot be resubmitted.
         test::set_caller::<DefaultEnvironment>(accounts.bob);
-        test::set_value_transferred::<DefaultEnvironment>(price);
-        test::set_block_timestamp::<DefaultEnvironment>(2100); // after start_time
+        assert_eq!(
+            contract.submit_assignment(course_id, quiz, String::from("again")),
+            Err(eduverse::Error::AlreadyGraded)
+        );
 
-        let enroll_result = contract.enroll(course_id);
-        assert_eq!(enroll_result, Err(eduverse::Error::CourseInProgress));
+        // Once Bob completed the course, neither work nor grades change.
+        test::set_caller::<DefaultEnvironment>(accounts.alice);
+        test::set_block_timestamp::<DefaultEnvironment>(3000);
+        assert!(contract.complete_course(course_id, accounts.bob).is_ok());
+        test::set_caller::<DefaultEnvironment>(accounts.charlie);
+        assert_eq!(
+            contract.grade_submission(course_id, project, accounts.bob, 100),
+            Err(eduverse::Error::EnrollmentNotActive)
+        );
+        test::set_caller::<DefaultEnvironment>(accounts.bob);
+        assert_eq!(
+            contract.submit_assignment(course_id, project, String::from("again")),
+            Err(eduverse::Error::EnrollmentNotActive)
+        );
+        assert_eq!(contract.get_aggregate_score(course_id, accounts.bob), 5_750);
     }
 
-    /// Test enrolling a student who is already enrolled.
+    /// Test students claiming their certificate once the completion rule is met.
     #[ink::test]
-    fn test_already_enrolled() {
+    fn test_claim_certificate() {
         let accounts = test::default_accounts::<DefaultEnvironment>();
-        // Create a course as Alice.
         test::set_caller::<DefaultEnvironment>(accounts.alice);
         test::set_block_timestamp::<DefaultEnvironment>(1000);
         let mut contract = Eduverse::new();
+        let course_id = create_test_course(&mut contract, MAX_STUDENTS);
+        let lesson = contract
+            .add_lesson(
+                course_id,
+                0,
+                String::from("Intro"),
+                String::from(METADATA_HASH),
+                None,
+            )
+            .unwrap();
+        let assignment = contract
+            .add_assignment(course_id, String::from("Essay"), 2500, 10, 1)
+            .unwrap();
+
+        // A second course asks for every lesson but has none.
+        let empty_course = create_test_course(&mut contract, MAX_STUDENTS);
+        assert!(contract
+            .set_completion_rule(
+                empty_course,
+                Some(eduverse::CompletionRule {
+                    min_score: None,
+                    all_lessons: true,
+                    min_attendance: None,
+                })
+            )
+            .is_ok());
 
-        let title = String::from("Double Enrollment Course");
-        let description = String::from("Test already enrolled.");
-        let start_time = 2000;
-        let end_time = 3000;
-        let price = PRICE;
-        let metadata_hash = String::from("double_hash");
+        test::set_caller::<DefaultEnvironment>(accounts.bob);
+        test::set_value_transferred::<DefaultEnvironment>(PRICE);
+        assert!(contract.enroll(course_id).is_ok());
+        assert!(contract.enroll(empty_course).is_ok());
+        assert_eq!(
+            contract.claim_certificate(course_id),
+            Err(eduverse::Error::NoCompletionRule)
+        );
 
-        let course_id = contract
-            .create_course(
-                title,
-                description,
-                2, // allow 2 students
-                start_time,
-                end_time,
-                price,
-                metadata_hash,
-            )
-            .expect("Course creation should succeed");
+        // A rule needs at least one criterion.
+        test::set_caller::<DefaultEnvironment>(accounts.alice);
+        assert_eq!(
+            contract.set_completion_rule(
+                course_id,
+                Some(eduverse::CompletionRule {
+                    min_score: None,
+                    all_lessons: false,
+                    min_attendance: None,
+                })
+            ),
+            Err(eduverse::Error::InvalidInput)
+        );
 
-        // Bob enrolls successfully.
+        // Alice requires 60% and every lesson.
+        let rule = eduverse::CompletionRule {
+            min_score: Some(6_000),
+            all_lessons: true,
+            min_attendance: None,
+        };
+        assert!(contract
+            .set_completion_rule(course_id, Some(rule.clone()))
+            .is_ok());
+        assert_eq!(
+            contract.get_course(course_id).unwrap().completion_rule,
+            Some(rule)
+        );
+
+        // Bob does the work, but has to wait for the end of the course.
         test::set_caller::<DefaultEnvironment>(accounts.bob);
-        test::set_value_transferred::<DefaultEnvironment>(price);
-        test::set_block_timestamp::<DefaultEnvironment>(1500);
-        let enroll_result = contract.enroll(course_id);
-        assert!(enroll_result.is_ok());
+        test::set_block_timestamp::<DefaultEnvironment>(2200);
+        assert!(contract.mark_lesson_complete(course_id, lesson).is_ok());
+        assert!(contract
+            .submit_assignment(course_id, assignment, String::from("essay_hash"))
+            .is_ok());
+        assert_eq!(
+            contract.claim_certificate(course_id),
+            Err(eduverse::Error::CourseInProgress)
+        );
 
-        // Bob tries to enroll again.
-        test::set_value_transferred::<DefaultEnvironment>(price);
-        let second_enroll = contract.enroll(course_id);
-        assert_eq!(second_enroll, Err(eduverse::Error::AlreadyEnrolled));
+        // Once it ended, the course without lessons still cannot be claimed and the
+        // essay has to be graded.
+        test::set_block_timestamp::<DefaultEnvironment>(3000);
+        assert_eq!(
+            contract.claim_certificate(empty_course),
+            Err(eduverse::Error::CompletionRuleNotMet)
+        );
+        assert_eq!(
+            contract.claim_certificate(course_id),
+            Err(eduverse::Error::CompletionRuleNotMet)
+        );
+
+        test::set_caller::<DefaultEnvironment>(accounts.alice);
+        assert!(contract
+            .grade_submission(course_id, assignment, accounts.bob, 7)
+            .is_ok());
+
+        // At 70% Bob can mint the certificate.
+        test::set_caller::<DefaultEnvironment>(accounts.bob);
+        let certificate_id = contract.claim_certificate(course_id).unwrap();
+        assert!(contract.verify_completion(accounts.bob, course_id));
+        assert_eq!(contract.owner_of(certificate_id), Some(accounts.bob));
+        assert_eq!(
+            contract.claim_certificate(course_id),
+            Err(eduverse::Error::AlreadyCompleted)
+        );
     }
 
-    /// Test enrolling a student when the course is full.
+    /// Test scoring a quiz through commit and reveal.
     #[ink::test]
-    fn test_course_full() {
+    fn test_quiz_commit_reveal() {
         let accounts = test::default_accounts::<DefaultEnvironment>();
-        // Create a course as Alice with max_students = 1.
         test::set_caller::<DefaultEnvironment>(accounts.alice);
         test::set_block_timestamp::<DefaultEnvironment>(1000);
         let mut contract = Eduverse::new();
+        let course_id = create_test_course(&mut contract, MAX_STUDENTS);
+
+        let key = vec![1, 2, 3];
+        let key_salt = [7u8; 32];
+        let key_commitment = Eduverse::quiz_commitment(course_id, 0, None, &key, &key_salt);
+
+        // The quiz weighs three times an essay and must carry some weight.
+        assert!(contract
+            .add_assignment(course_id, String::from("Essay"), 2500, 10, 1)
+            .is_ok());
+        assert_eq!(
+            contract.create_quiz(course_id, 3, 0, key_commitment, 2500, 2900),
+            Err(eduverse::Error::InvalidInput)
+        );
+        let quiz_id = contract
+            .create_quiz(course_id, 3, 3, key_commitment, 2500, 2900)
+            .unwrap();
+
+        // Bob commits, Charlie copies Bob's commitment from the mempool.
+        let answers = vec![1, 2, 0];
+        let salt = [9u8; 32];
+        let commitment =
+            Eduverse::quiz_commitment(course_id, quiz_id, Some(accounts.bob), &answers, &salt);
+        test::set_value_transferred::<DefaultEnvironment>(PRICE);
+        for student in [accounts.bob, accounts.charlie] {
+            test::set_caller::<DefaultEnvironment>(student);
+            assert!(contract.enroll(course_id).is_ok());
+        }
+        test::set_block_timestamp::<DefaultEnvironment>(2200);
+        for student in [accounts.bob, accounts.charlie] {
+            test::set_caller::<DefaultEnvironment>(student);
+            assert!(contract
+                .commit_quiz_answers(course_id, quiz_id, commitment)
+                .is_ok());
+        }
 
-        let title = String::from("Full Course");
-        let description = String::from("Test course full.");
-        let start_time = 2000;
-        let end_time = 3000;
-        let price = PRICE;
-        let metadata_hash = String::from("full_hash");
-
-        let course_id = contract
-            .create_course(
-                title,
-                description,
-                1, // only one student allowed
-                start_time,
-                end_time,
-                price,
-                metadata_hash,
-            )
-            .expect("Course creation should succeed");
+        // The key stays secret until commitments are closed.
+        test::set_caller::<DefaultEnvironment>(accounts.alice);
+        assert_eq!(
+            contract.reveal_answer_key(course_id, quiz_id, key.clone(), key_salt),
+            Err(eduverse::Error::QuizStillOpen)
+        );
+        test::set_block_timestamp::<DefaultEnvironment>(2600);
+        assert_eq!(
+            contract.reveal_answer_key(course_id, quiz_id, vec![3, 2, 1], key_salt),
+            Err(eduverse::Error::CommitmentMismatch)
+        );
+        assert!(contract
+            .reveal_answer_key(course_id, quiz_id, key, key_salt)
+            .is_ok());
 
-        // Bob enrolls successfully.
+        // Students reveal in their own window, past the key's deadline.
         test::set_caller::<DefaultEnvironment>(accounts.bob);
-        test::set_value_transferred::<DefaultEnvironment>(price);
-        test::set_block_timestamp::<DefaultEnvironment>(1500);
-        let enroll_result = contract.enroll(course_id);
-        assert!(enroll_result.is_ok());
+        test::set_block_timestamp::<DefaultEnvironment>(2950);
+        assert_eq!(
+            contract.reveal_quiz_answers(course_id, quiz_id, vec![1, 2], salt),
+            Err(eduverse::Error::InvalidInput)
+        );
+        assert_eq!(
+            contract.reveal_quiz_answers(course_id, quiz_id, answers.clone(), salt),
+            Ok(2)
+        );
+        assert_eq!(
+            contract.get_quiz_score(course_id, quiz_id, accounts.bob),
+            Some(2)
+        );
+        assert_eq!(contract.get_quiz_points(course_id, accounts.bob), 2);
 
-        // Charlie attempts to enroll and should fail.
+        // The score counts towards the aggregate used by completion rules,
+        // (66.7% * 3 + 0% * 1) / 4 = 50%.
+        assert_eq!(contract.get_aggregate_score(course_id, accounts.bob), 5_000);
+
+        // The copied commitment is bound to Bob's account.
         test::set_caller::<DefaultEnvironment>(accounts.charlie);
-        test::set_value_transferred::<DefaultEnvironment>(price);
-        let enroll_result_charlie = contract.enroll(course_id);
-        assert_eq!(enroll_result_charlie, Err(eduverse::Error::CourseIsFull));
+        assert_eq!(
+            contract.reveal_quiz_answers(course_id, quiz_id, answers.clone(), salt),
+            Err(eduverse::Error::CommitmentMismatch)
+        );
+
+        // Failed students no longer commit or reveal.
+        test::set_caller::<DefaultEnvironment>(accounts.alice);
+        test::set_block_timestamp::<DefaultEnvironment>(3000);
+        assert!(contract.fail_student(course_id, accounts.charlie).is_ok());
+        test::set_caller::<DefaultEnvironment>(accounts.charlie);
+        assert_eq!(
+            contract.commit_quiz_answers(course_id, quiz_id, commitment),
+            Err(eduverse::Error::EnrollmentNotActive)
+        );
+        assert_eq!(
+            contract.reveal_quiz_answers(course_id, quiz_id, answers, salt),
+            Err(eduverse::Error::EnrollmentNotActive)
+        );
+
+        // The window closes a fixed time after the key was revealed.
+        test::set_caller::<DefaultEnvironment>(accounts.bob);
+        test::set_block_timestamp::<DefaultEnvironment>(2600 + eduverse::QUIZ_REVEAL_WINDOW);
+        assert_eq!(
+            contract.reveal_quiz_answers(course_id, quiz_id, vec![1, 2, 3], salt),
+            Err(eduverse::Error::QuizClosed)
+        );
     }
 
-    /// Test updating a course after it has already started.
+    /// Test attendance recorded by an oracle counting towards completion.
     #[ink::test]
-    fn test_update_course_after_start() {
+    fn test_record_attendance() {
         let accounts = test::default_accounts::<DefaultEnvironment>();
-        // Create a course as Alice.
         test::set_caller::<DefaultEnvironment>(accounts.alice);
         test::set_block_timestamp::<DefaultEnvironment>(1000);
         let mut contract = Eduverse::new();
+        let course_id = create_test_course(&mut contract, MAX_STUDENTS);
 
-        let title = String::from("Update Timing Course");
-        let description = String::from("Test update after start.");
-        let start_time = 2000;
-        let end_time = 3000;
-        let price = PRICE;
-        let metadata_hash = String::from("update_hash");
-
-        let course_id = contract
-            .create_course(
-                title,
-                description,
-                MAX_STUDENTS,
-                start_time,
-                end_time,
-                price,
-                metadata_hash,
+        // Sessions are scheduled between the course start and end.
+        for starts_at in [1999, 3000] {
+            assert_eq!(
+                contract.add_session(course_id, String::from("Off schedule"), starts_at),
+                Err(eduverse::Error::InvalidTime)
+            );
+        }
+        let first = contract
+            .add_session(course_id, String::from("Kickoff"), 2000)
+            .unwrap();
+        let second = contract
+            .add_session(course_id, String::from("Workshop"), 2500)
+            .unwrap();
+        assert!(contract
+            .set_completion_rule(
+                course_id,
+                Some(eduverse::CompletionRule {
+                    min_score: None,
+                    all_lessons: false,
+                    min_attendance: Some(2),
+                })
             )
-            .expect("Course creation should succeed");
+            .is_ok());
 
-        // Advance time past the start time.
+        test::set_caller::<DefaultEnvironment>(accounts.bob);
+        test::set_value_transferred::<DefaultEnvironment>(PRICE);
+        assert!(contract.enroll(course_id).is_ok());
+
+        // Only the owner registers oracles, only oracles and the teacher record.
+        assert_eq!(
+            contract.set_attendance_oracle(accounts.eve, true),
+            Err(eduverse::Error::NotOwner)
+        );
+        test::set_caller::<DefaultEnvironment>(accounts.eve);
         test::set_block_timestamp::<DefaultEnvironment>(2100);
+        assert_eq!(
+            contract.record_attendance(course_id, first, vec![accounts.bob]),
+            Err(eduverse::Error::Unauthorized)
+        );
+        test::set_caller::<DefaultEnvironment>(accounts.alice);
+        assert!(contract.set_attendance_oracle(accounts.eve, true).is_ok());
 
-        // Attempt to update the course.
-        let update_result = contract.update_course(
-            course_id,
-            Some(String::from("New Title")),
-            None,
-            None,
-            None,
-            None,
-            None,
+        // Charlie is not enrolled and a repeated Bob is only counted once.
+        test::set_caller::<DefaultEnvironment>(accounts.eve);
+        assert_eq!(
+            contract.record_attendance(
+                course_id,
+                first,
+                vec![accounts.bob, accounts.charlie, accounts.bob]
+            ),
+            Ok(1)
         );
-        assert_eq!(update_result, Err(eduverse::Error::CourseInProgress));
+        assert_eq!(
+            contract.record_attendance(course_id, second, vec![accounts.bob]),
+            Err(eduverse::Error::SessionNotStarted)
+        );
+        assert!(contract.has_attended(course_id, first, accounts.bob));
+        assert_eq!(contract.get_attendance_count(course_id, accounts.bob), 1);
+
+        // Certificates are only claimed after the course ended.
+        test::set_caller::<DefaultEnvironment>(accounts.bob);
+        assert_eq!(
+            contract.claim_certificate(course_id),
+            Err(eduverse::Error::CourseInProgress)
+        );
+
+        // Co-teachers record attendance like the teacher.
+        test::set_caller::<DefaultEnvironment>(accounts.alice);
+        assert!(contract
+            .grant_role(course_id, accounts.django, eduverse::CourseRole::CoTeacher)
+            .is_ok());
+        test::set_caller::<DefaultEnvironment>(accounts.django);
+        test::set_block_timestamp::<DefaultEnvironment>(2600);
+        assert_eq!(
+            contract.record_attendance(course_id, second, vec![accounts.bob]),
+            Ok(1)
+        );
+
+        // So do teaching assistants, Bob was already recorded for both sessions.
+        test::set_caller::<DefaultEnvironment>(accounts.alice);
+        assert!(contract
+            .grant_role(
+                course_id,
+                accounts.frank,
+                eduverse::CourseRole::TeachingAssistant
+            )
+            .is_ok());
+        test::set_caller::<DefaultEnvironment>(accounts.frank);
+        assert_eq!(
+            contract.record_attendance(course_id, first, vec![accounts.bob]),
+            Ok(0)
+        );
+        test::set_caller::<DefaultEnvironment>(accounts.bob);
+        test::set_block_timestamp::<DefaultEnvironment>(3000);
+        assert!(contract.claim_certificate(course_id).is_ok());
     }
 
-    /// Test completing a course by someone who is not the teacher.
+    /// Test that a course can no longer be cancelled once it ended or its payments were released.
     #[ink::test]
-    fn test_complete_course_non_teacher() {
+    fn test_cancel_course_after_release() {
         let accounts = test::default_accounts::<DefaultEnvironment>();
-        // Create a course as Alice.
         test::set_caller::<DefaultEnvironment>(accounts.alice);
         test::set_block_timestamp::<DefaultEnvironment>(1000);
         let mut contract = Eduverse::new();
+        let released_course = create_test_course(&mut contract, MAX_STUDENTS);
+        let ended_course = create_test_course(&mut contract, MAX_STUDENTS);
 
-        let title = String::from("Non-Teacher Completion Course");
-        let description = String::from("Test complete course by non-teacher.");
-        let start_time = 2000;
-        let end_time = 3000;
-        let price = PRICE;
-        let metadata_hash = String::from("nonteacher_hash");
-
-        let course_id = contract
-            .create_course(
-                title,
-                description,
-                MAX_STUDENTS,
-                start_time,
-                end_time,
-                price,
-                metadata_hash,
-            )
-            .expect("Course creation should succeed");
-
-        // Bob enrolls.
         test::set_caller::<DefaultEnvironment>(accounts.bob);
-        test::set_value_transferred::<DefaultEnvironment>(price);
-        test::set_block_timestamp::<DefaultEnvironment>(1500);
-        let enroll_result = contract.enroll(course_id);
-        assert!(enroll_result.is_ok());
+        test::set_value_transferred::<DefaultEnvironment>(PRICE);
+        assert!(contract.enroll(released_course).is_ok());
+        assert!(contract.enroll(ended_course).is_ok());
+        test::set_value_transferred::<DefaultEnvironment>(0);
 
-        // Advance time past the end time.
-        test::set_block_timestamp::<DefaultEnvironment>(3500);
+        // The refund window closes and Alice is paid for the first course.
+        test::set_block_timestamp::<DefaultEnvironment>(2500);
+        test::set_caller::<DefaultEnvironment>(accounts.alice);
+        assert!(contract.release_payments(released_course).is_ok());
+        assert_eq!(
+            contract.cancel_course(released_course, String::from("reason")),
+            Err(eduverse::Error::RefundPeriodEnded)
+        );
 
-        // Bob (not the teacher) attempts to mark the course complete.
-        test::set_caller::<DefaultEnvironment>(accounts.bob);
-        let complete_result = contract.complete_course(course_id, accounts.bob);
-        assert_eq!(complete_result, Err(eduverse::Error::Unauthorized));
+        // The second course runs to its end without a release.
+        test::set_block_timestamp::<DefaultEnvironment>(3000);
+        assert_eq!(
+            contract.cancel_course(ended_course, String::from("reason")),
+            Err(eduverse::Error::CourseEnded)
+        );
+        assert_eq!(contract.get_course_escrow(ended_course), PRICE);
+        assert!(!contract.get_course(ended_course).unwrap().cancelled);
     }
 
-    /// Test completing a course before the course has ended.
+    /// Test offering seats to students who joined the waitlist without escrow.
     #[ink::test]
-    fn test_complete_course_before_end() {
+    fn test_waitlist_seat_offers() {
         let accounts = test::default_accounts::<DefaultEnvironment>();
-        // Create a course as Alice.
         test::set_caller::<DefaultEnvironment>(accounts.alice);
         test::set_block_timestamp::<DefaultEnvironment>(1000);
         let mut contract = Eduverse::new();
+        let course_id = create_test_course(&mut contract, 1);
 
-        let title = String::from("Early Completion Course");
-        let description = String::from("Test complete course too early.");
-        let start_time = 2000;
-        let end_time = 3000;
-        let price = PRICE;
-        let metadata_hash = String::from("early_hash");
-
-        let course_id = contract
-            .create_course(
-                title,
-                description,
-                MAX_STUDENTS,
-                start_time,
-                end_time,
-                price,
-                metadata_hash,
-            )
-            .expect("Course creation should succeed");
+        test::set_caller::<DefaultEnvironment>(accounts.bob);
+        test::set_value_transferred::<DefaultEnvironment>(PRICE);
+        assert!(contract.enroll(course_id).is_ok());
+
+        // Charlie and Frank wait without paying, Django escrows the price.
+        for student in [accounts.charlie, accounts.django, accounts.frank] {
+            test::set_caller::<DefaultEnvironment>(student);
+            let escrow = student == accounts.django;
+            let value = if escrow { PRICE } else { 0 };
+            test::set_value_transferred::<DefaultEnvironment>(value);
+            assert!(contract.join_waitlist(course_id, escrow).is_ok());
+        }
+        assert_eq!(
+            contract
+                .get_waitlist_entry(course_id, accounts.charlie)
+                .unwrap()
+                .escrowed,
+            0
+        );
 
-        // Bob enrolls.
+        // Bob leaves and the seat is held for Charlie instead of enrolling Charlie.
         test::set_caller::<DefaultEnvironment>(accounts.bob);
-        test::set_value_transferred::<DefaultEnvironment>(price);
-        test::set_block_timestamp::<DefaultEnvironment>(1500);
-        let enroll_result = contract.enroll(course_id);
-        assert!(enroll_result.is_ok());
+        test::set_value_transferred::<DefaultEnvironment>(0);
+        assert!(contract.unenroll(course_id).is_ok());
+        assert!(!contract.verify_enrollment(accounts.charlie, course_id));
+        assert_eq!(
+            contract.get_seat_offer(course_id, accounts.charlie),
+            Some(1000 + eduverse::SEAT_OFFER_WINDOW)
+        );
+        assert_eq!(
+            contract.get_waitlist(course_id),
+            vec![accounts.django, accounts.frank]
+        );
 
-        // Advance time to between start and end.
-        test::set_block_timestamp::<DefaultEnvironment>(2500);
+        test::set_caller::<DefaultEnvironment>(accounts.eve);
+        test::set_value_transferred::<DefaultEnvironment>(PRICE);
+        assert_eq!(
+            contract.enroll(course_id),
+            Err(eduverse::Error::CourseIsFull)
+        );
 
-        // Teacher attempts to complete the course too early.
-        test::set_caller::<DefaultEnvironment>(accounts.alice);
-        let complete_result = contract.complete_course(course_id, accounts.bob);
-        assert_eq!(complete_result, Err(eduverse::Error::CourseInProgress));
+        // Charlie turns the seat down and Django is enrolled from escrow.
+        test::set_caller::<DefaultEnvironment>(accounts.charlie);
+        test::set_value_transferred::<DefaultEnvironment>(0);
+        assert!(contract.leave_waitlist(course_id).is_ok());
+        assert_eq!(contract.get_seat_offer(course_id, accounts.charlie), None);
+        assert!(contract.verify_enrollment(accounts.django, course_id));
+
+        // Django leaves and Frank pays for the offered seat.
+        test::set_caller::<DefaultEnvironment>(accounts.django);
+        assert!(contract.unenroll(course_id).is_ok());
+        test::set_caller::<DefaultEnvironment>(accounts.frank);
+        test::set_value_transferred::<DefaultEnvironment>(PRICE);
+        assert!(contract.enroll(course_id).is_ok());
+        assert_eq!(contract.get_seat_offer(course_id, accounts.frank), None);
+        assert_eq!(
+            contract.get_escrowed_payment(course_id, accounts.frank),
+            PRICE
+        );
+        assert_eq!(contract.get_course(course_id).unwrap().enrolled_count, 1);
+        assert!(contract.get_waitlist(course_id).is_empty());
     }
 
-    /// Test that NFT transfers are rejected.
+    /// Test that one promotion visits at most `MAX_WAITLIST_STEPS` waitlist positions.
     #[ink::test]
-    fn test_transfer_rejection() {
+    fn test_waitlist_promotion_step_limit() {
         let accounts = test::default_accounts::<DefaultEnvironment>();
-        // Create a course and complete it to mint an NFT.
         test::set_caller::<DefaultEnvironment>(accounts.alice);
         test::set_block_timestamp::<DefaultEnvironment>(1000);
         let mut contract = Eduverse::new();
+        let course_id = create_test_course(&mut contract, 1);
 
-        let title = String::from("Transfer Test Course");
-        let description = String::from("Test NFT transfer rejection.");
-        let start_time = 2000;
-        let end_time = 3000;
-        let price = PRICE;
-        let metadata_hash = String::from("transfer_hash");
+        test::set_caller::<DefaultEnvironment>(accounts.bob);
+        test::set_value_transferred::<DefaultEnvironment>(PRICE);
+        assert!(contract.enroll(course_id).is_ok());
+        test::set_value_transferred::<DefaultEnvironment>(0);
+
+        // All but the last student leave again, leaving a run of empty positions.
+        let students: Vec<AccountId> = (0..=eduverse::MAX_WAITLIST_STEPS)
+            .map(|i| AccountId::from([0x80 + i as u8; 32]))
+            .collect();
+        for student in students.iter() {
+            test::set_caller::<DefaultEnvironment>(*student);
+            assert!(contract.join_waitlist(course_id, false).is_ok());
+        }
+        let last = *students.last().unwrap();
+        for student in students.iter().filter(|student| **student != last) {
+            test::set_caller::<DefaultEnvironment>(*student);
+            assert!(contract.leave_waitlist(course_id).is_ok());
+        }
+
+        // Bob's seat frees up, but the promotion stops after the empty positions.
+        test::set_caller::<DefaultEnvironment>(accounts.bob);
+        assert!(contract.unenroll(course_id).is_ok());
+        assert_eq!(contract.get_seat_offer(course_id, last), None);
+
+        // Anyone can carry the promotion on.
+        test::set_caller::<DefaultEnvironment>(accounts.eve);
+        assert!(contract.process_waitlist(course_id).is_ok());
+        assert_eq!(
+            contract.get_seat_offer(course_id, last),
+            Some(1000 + eduverse::SEAT_OFFER_WINDOW)
+        );
+    }
+
+    /// Test that expired seat offers are taken back and that offers lapse on cancellation.
+    #[ink::test]
+    fn test_seat_offer_expiry() {
+        let accounts = test::default_accounts::<DefaultEnvironment>();
+        test::set_caller::<DefaultEnvironment>(accounts.alice);
+        test::set_block_timestamp::<DefaultEnvironment>(1000);
+        let mut contract = Eduverse::new();
 
+        // The course starts long enough after the offers for them to run out.
+        let start_time = 1000 + 2 * eduverse::SEAT_OFFER_WINDOW;
         let course_id = contract
             .create_course(
-                title,
-                description,
-                MAX_STUDENTS,
+                String::from(COURSE_TITLE),
+                String::from(COURSE_DESC),
+                1,
                 start_time,
-                end_time,
-                price,
-                metadata_hash,
+                start_time + 1000,
+                PRICE,
+                String::from(METADATA_HASH),
+                REFUND_WINDOW,
+                None,
+                Vec::new(),
             )
-            .expect("Course creation should succeed");
+            .unwrap();
 
-        // Bob enrolls.
         test::set_caller::<DefaultEnvironment>(accounts.bob);
-        test::set_value_transferred::<DefaultEnvironment>(price);
-        test::set_block_timestamp::<DefaultEnvironment>(1500);
-        let enroll_result = contract.enroll(course_id);
-        assert!(enroll_result.is_ok());
+        test::set_value_transferred::<DefaultEnvironment>(PRICE);
+        assert!(contract.enroll(course_id).is_ok());
+        test::set_caller::<DefaultEnvironment>(accounts.charlie);
+        test::set_value_transferred::<DefaultEnvironment>(0);
+        assert!(contract.join_waitlist(course_id, false).is_ok());
+        test::set_caller::<DefaultEnvironment>(accounts.django);
+        test::set_value_transferred::<DefaultEnvironment>(PRICE);
+        assert!(contract.join_waitlist(course_id, true).is_ok());
 
-        // Advance time past course end.
-        test::set_block_timestamp::<DefaultEnvironment>(3500);
+        // Bob leaves and Charlie lets the offered seat run out.
+        test::set_caller::<DefaultEnvironment>(accounts.bob);
+        test::set_value_transferred::<DefaultEnvironment>(0);
+        assert!(contract.unenroll(course_id).is_ok());
+        test::set_block_timestamp::<DefaultEnvironment>(1000 + eduverse::SEAT_OFFER_WINDOW);
+        assert_eq!(contract.get_seat_offer(course_id, accounts.charlie), None);
+        test::set_caller::<DefaultEnvironment>(accounts.charlie);
+        test::set_value_transferred::<DefaultEnvironment>(PRICE);
+        assert_eq!(
+            contract.enroll(course_id),
+            Err(eduverse::Error::CourseIsFull)
+        );
 
-        // Teacher completes the course, minting the NFT.
+        // Anyone takes the seat back and it goes to Django.
+        test::set_caller::<DefaultEnvironment>(accounts.eve);
+        test::set_value_transferred::<DefaultEnvironment>(0);
+        assert!(contract.process_waitlist(course_id).is_ok());
+        assert!(contract.verify_enrollment(accounts.django, course_id));
+        assert!(!contract.verify_enrollment(accounts.charlie, course_id));
+
+        // Frank waits without paying, Eve escrows the price.
+        for (student, escrow) in [(accounts.frank, false), (accounts.eve, true)] {
+            test::set_caller::<DefaultEnvironment>(student);
+            let value = if escrow { PRICE } else { 0 };
+            test::set_value_transferred::<DefaultEnvironment>(value);
+            assert!(contract.join_waitlist(course_id, escrow).is_ok());
+        }
+        test::set_caller::<DefaultEnvironment>(accounts.django);
+        test::set_value_transferred::<DefaultEnvironment>(0);
+        assert!(contract.unenroll(course_id).is_ok());
+        assert!(contract.get_seat_offer(course_id, accounts.frank).is_some());
+
+        // Cancelling lapses Frank's offer and Eve takes the escrow back with `leave_waitlist`.
         test::set_caller::<DefaultEnvironment>(accounts.alice);
-        let complete_result = contract.complete_course(course_id, accounts.bob);
-        assert!(complete_result.is_ok());
+        assert!(contract
+            .cancel_course(course_id, String::from("reason"))
+            .is_ok());
+        assert_eq!(contract.get_seat_offer(course_id, accounts.frank), None);
+        assert_eq!(
+            contract
+                .get_waitlist_entry(course_id, accounts.eve)
+                .unwrap()
+                .escrowed,
+            PRICE
+        );
+        test::set_caller::<DefaultEnvironment>(accounts.eve);
+        assert!(contract.leave_waitlist(course_id).is_ok());
+        assert_eq!(contract.get_waitlist_entry(course_id, accounts.eve), None);
+    }
+}
 
-        // Try transferring the NFT (should be rejected).
-        let bob_certs = contract.get_student_certificate_vector(accounts.bob);
-        assert!(!bob_certs.is_empty());
-        let certificate_id = bob_certs[0].clone();
-        // Attempt to transfer from Bob to Charlie.
-        test::set_caller::<DefaultEnvironment>(accounts.bob);
-        let transfer_result = contract.transfer(accounts.charlie, certificate_id, Vec::new());
-        match transfer_result {
-            Err(PSP34Error::Custom(ref msg)) => {
-                assert_eq!(msg, "Certificates cannot be transferred");
+#[cfg(all(test, feature = "e2e-tests"))]
+mod e2e_tests {
+    use super::eduverse::{Eduverse, EduverseRef, Error};
+    use ink_e2e::ContractsBackend;
+    use test_token::{Token, TokenRef, PSP22};
+
+    type E2EResult<T> = std::result::Result<T, Box<dyn std::error::Error>>;
+
+    /// Test that courses survive swapping the contract code in place.
+    #[ink_e2e::test]
+    async fn upgrade_preserves_state<Client: E2EBackend>(mut client: Client) -> E2EResult<()> {
+        let mut constructor = EduverseRef::new();
+        let contract = client
+            .instantiate("course_management", &ink_e2e::alice(), &mut constructor)
+            .submit()
+            .await
+            .expect("instantiate failed");
+        let mut call_builder = contract.call_builder::<Eduverse>();
+
+        // Far future times so the course is valid against the chain clock.
+        let start_time = u64::MAX / 2;
+        let create_course = call_builder.create_course(
+            String::from("Upgradeable Course"),
+            String::from("Survives upgrades."),
+            10,
+            start_time,
+            start_time + 1000,
+            100,
+            String::from("upgrade_hash"),
+            0,
+            None,
+            Vec::new(),
+        );
+        client
+            .call(&ink_e2e::alice(), &create_course)
+            .submit()
+            .await
+            .expect("create_course failed");
+
+        // Upload the code again and point the contract at it.
+        let code_hash = client
+            .upload("course_management", &ink_e2e::alice())
+            .submit()
+            .await
+            .expect("upload failed")
+            .code_hash;
+        let upgrade = call_builder.upgrade(code_hash);
+        let result = client
+            .call(&ink_e2e::alice(), &upgrade)
+            .submit()
+            .await
+            .expect("upgrade failed");
+        assert!(result.return_value().is_ok());
+
+        let get_course = call_builder.get_course(0);
+        let course = client
+            .call(&ink_e2e::alice(), &get_course)
+            .dry_run()
+            .await?
+            .return_value()
+            .expect("course should survive the upgrade");
+        assert_eq!(course.title, "Upgradeable Course");
+
+        let get_owner = call_builder.get_owner();
+        let owner = client
+            .call(&ink_e2e::alice(), &get_owner)
+            .dry_run()
+            .await?
+            .return_value();
+        assert_eq!(owner, ink_e2e::account_id(ink_e2e::AccountKeyring::Alice));
+
+        Ok(())
+    }
+
+    /// Test paying for and refunding a course priced in a deployed PSP22 token.
+    #[ink_e2e::test]
+    async fn token_course_payment<Client: E2EBackend>(mut client: Client) -> E2EResult<()> {
+        let supply = 1000;
+        let price = 100;
+        let mut token_constructor = TokenRef::new(supply);
+        let token = client
+            .instantiate("test_token", &ink_e2e::alice(), &mut token_constructor)
+            .submit()
+            .await
+            .expect("token instantiate failed");
+        let mut token_calls = token.call_builder::<Token>();
+
+        let mut constructor = EduverseRef::new();
+        let contract = client
+            .instantiate("course_management", &ink_e2e::alice(), &mut constructor)
+            .submit()
+            .await
+            .expect("instantiate failed");
+        let mut call_builder = contract.call_builder::<Eduverse>();
+
+        let alice = ink_e2e::account_id(ink_e2e::AccountKeyring::Alice);
+        let bob = ink_e2e::account_id(ink_e2e::AccountKeyring::Bob);
+        let start_time = u64::MAX / 2;
+        let mut create_course = |payment_token| {
+            call_builder.create_course(
+                String::from("Token Course"),
+                String::from("Paid in a PSP22 token."),
+                10,
+                start_time,
+                start_time + 1000,
+                price,
+                String::from("token_hash"),
+                500,
+                Some(payment_token),
+                Vec::new(),
+            )
+        };
+
+        // Neither Eduverse itself nor a plain account is accepted as the token.
+        for payment_token in [contract.account_id, bob] {
+            let result = client
+                .call(&ink_e2e::bob(), &create_course(payment_token))
+                .dry_run()
+                .await?;
+            assert!(matches!(
+                result.return_value(),
+                Err(Error::InvalidPaymentToken)
+            ));
+        }
+
+        let course_id = client
+            .call(&ink_e2e::bob(), &create_course(token.account_id))
+            .submit()
+            .await
+            .expect("create_course failed")
+            .return_value()
+            .expect("token course should be created");
+
+        // Alice approves the price and pays for her seat in the token.
+        let approve = token_calls.approve(contract.account_id, price);
+        client
+            .call(&ink_e2e::alice(), &approve)
+            .submit()
+            .await
+            .expect("approve failed");
+        let enroll = call_builder.enroll_with_token(course_id);
+        client
+            .call(&ink_e2e::alice(), &enroll)
+            .submit()
+            .await
+            .expect("enroll_with_token failed");
+
+        let escrow = token_calls.balance_of(contract.account_id);
+        let escrowed = client
+            .call(&ink_e2e::alice(), &escrow)
+            .dry_run()
+            .await?
+            .return_value();
+        assert_eq!(escrowed, price);
+
+        // The refund is paid back in the token.
+        let refund = call_builder.request_refund(course_id);
+        client
+            .call(&ink_e2e::alice(), &refund)
+            .submit()
+            .await
+            .expect("request_refund failed");
+
+        let balance = token_calls.balance_of(alice);
+        let refunded = client
+            .call(&ink_e2e::alice(), &balance)
+            .dry_run()
+            .await?
+            .return_value();
+        assert_eq!(refunded, supply);
+
+        Ok(())
+    }
+
+    /// Test releasing a token course's escrow, including a seat paid from waitlist escrow,
+    /// and withdrawing the teacher's and the platform's shares in the token.
+    #[ink_e2e::test]
+    async fn token_course_release<Client: E2EBackend>(mut client: Client) -> E2EResult<()> {
+        let supply = 1000;
+        let price = 100;
+        let mut token_constructor = TokenRef::new(supply);
+        let token = client
+            .instantiate("test_token", &ink_e2e::alice(), &mut token_constructor)
+            .submit()
+            .await
+            .expect("token instantiate failed");
+        let mut token_calls = token.call_builder::<Token>();
+
+        let mut constructor = EduverseRef::new();
+        let contract = client
+            .instantiate("course_management", &ink_e2e::alice(), &mut constructor)
+            .submit()
+            .await
+            .expect("instantiate failed");
+        let mut call_builder = contract.call_builder::<Eduverse>();
+
+        let alice = ink_e2e::account_id(ink_e2e::AccountKeyring::Alice);
+        let bob = ink_e2e::account_id(ink_e2e::AccountKeyring::Bob);
+        let charlie = ink_e2e::account_id(ink_e2e::AccountKeyring::Charlie);
+
+        // A 10% platform fee is fixed into the course when it is created.
+        let set_fee = call_builder.set_platform_fee(1000);
+        client
+            .call(&ink_e2e::alice(), &set_fee)
+            .submit()
+            .await
+            .expect("set_platform_fee failed");
+
+        // The node stamps blocks with wall time, so the course starts shortly and its
+        // escrow can be released once the chain clock passes the start.
+        let now = std::time::SystemTime::now().duration_since(std::time::UNIX_EPOCH)?;
+        let start_time = u64::try_from(now.as_millis())? + 20_000;
+        let create_course = call_builder.create_course(
+            String::from("Token Course"),
+            String::from("Paid in a PSP22 token."),
+            1,
+            start_time,
+            start_time + 1000,
+            price,
+            String::from("token_hash"),
+            0,
+            Some(token.account_id),
+            Vec::new(),
+        );
+        let course_id = client
+            .call(&ink_e2e::bob(), &create_course)
+            .submit()
+            .await
+            .expect("create_course failed")
+            .return_value()
+            .expect("token course should be created");
+
+        // Alice takes the only seat and Charlie waits with the price in escrow.
+        let fund_charlie = token_calls.transfer(charlie, price, Vec::new());
+        client
+            .call(&ink_e2e::alice(), &fund_charlie)
+            .submit()
+            .await
+            .expect("transfer failed");
+        let approve = token_calls.approve(contract.account_id, price);
+        for caller in [ink_e2e::alice(), ink_e2e::charlie()] {
+            client
+                .call(&caller, &approve)
+                .submit()
+                .await
+                .expect("approve failed");
+        }
+        let enroll = call_builder.enroll_with_token(course_id);
+        client
+            .call(&ink_e2e::alice(), &enroll)
+            .submit()
+            .await
+            .expect("enroll_with_token failed");
+        let join_waitlist = call_builder.join_waitlist(course_id, true);
+        client
+            .call(&ink_e2e::charlie(), &join_waitlist)
+            .submit()
+            .await
+            .expect("join_waitlist failed");
+
+        let escrow = token_calls.balance_of(contract.account_id);
+        let escrowed = client
+            .call(&ink_e2e::alice(), &escrow)
+            .dry_run()
+            .await?
+            .return_value();
+        assert_eq!(escrowed, 2 * price);
+
+        // Alice leaves, is paid back and Charlie is enrolled from the waitlist escrow.
+        let unenroll = call_builder.unenroll(course_id);
+        client
+            .call(&ink_e2e::alice(), &unenroll)
+            .submit()
+            .await
+            .expect("unenroll failed");
+        let verify = call_builder.verify_enrollment(charlie, course_id);
+        let enrolled = client
+            .call(&ink_e2e::charlie(), &verify)
+            .dry_run()
+            .await?
+            .return_value();
+        assert!(enrolled);
+        let escrowed = client
+            .call(&ink_e2e::alice(), &escrow)
+            .dry_run()
+            .await?
+            .return_value();
+        assert_eq!(escrowed, price);
+
+        // Blocks are only sealed for transactions, so each check submits one to move
+        // the chain clock on until the refund window has closed.
+        let release = call_builder.release_payments(course_id);
+        let tick = token_calls.approve(contract.account_id, 0);
+        let mut releasable = false;
+        for _ in 0..60 {
+            std::thread::sleep(std::time::Duration::from_secs(1));
+            client
+                .call(&ink_e2e::alice(), &tick)
+                .submit()
+                .await
+                .expect("approve failed");
+            let result = client.call(&ink_e2e::bob(), &release).dry_run().await?;
+            if result.return_value().is_ok() {
+                releasable = true;
+                break;
             }
-            _ => panic!("Transfer should be rejected"),
         }
+        assert!(releasable);
+        client
+            .call(&ink_e2e::bob(), &release)
+            .submit()
+            .await
+            .expect("release_payments failed");
+
+        // The teacher is credited the price less the fee, the treasury the fee.
+        let pending = call_builder.get_pending_token_withdrawal(bob, token.account_id);
+        let teacher_share = client
+            .call(&ink_e2e::bob(), &pending)
+            .dry_run()
+            .await?
+            .return_value();
+        assert_eq!(teacher_share, 90);
+        let treasury = call_builder.get_token_treasury_balance(token.account_id);
+        let fee = client
+            .call(&ink_e2e::alice(), &treasury)
+            .dry_run()
+            .await?
+            .return_value();
+        assert_eq!(fee, 10);
+
+        // Both shares are withdrawn in the token, leaving nothing behind.
+        let withdraw = call_builder.withdraw_token(token.account_id, teacher_share);
+        client
+            .call(&ink_e2e::bob(), &withdraw)
+            .submit()
+            .await
+            .expect("withdraw_token failed");
+        let withdraw_fee = call_builder.withdraw_token_treasury(token.account_id, fee);
+        client
+            .call(&ink_e2e::alice(), &withdraw_fee)
+            .submit()
+            .await
+            .expect("withdraw_token_treasury failed");
+
+        let bob_balance = token_calls.balance_of(bob);
+        let teacher_balance = client
+            .call(&ink_e2e::bob(), &bob_balance)
+            .dry_run()
+            .await?
+            .return_value();
+        assert_eq!(teacher_balance, 90);
+        let alice_balance = token_calls.balance_of(alice);
+        let owner_balance = client
+            .call(&ink_e2e::alice(), &alice_balance)
+            .dry_run()
+            .await?
+            .return_value();
+        assert_eq!(owner_balance, supply - price + 10);
+        let escrowed = client
+            .call(&ink_e2e::alice(), &escrow)
+            .dry_run()
+            .await?
+            .return_value();
+        assert_eq!(escrowed, 0);
+
+        Ok(())
     }
 }